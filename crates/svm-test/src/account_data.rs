@@ -0,0 +1,179 @@
+//! Encodings used by the typed account accessors on [`crate::Svm`].
+//!
+//! ```ignore
+//! let token = svm.get_as::<Packed<spl_token::state::Account>>(&key);
+//! svm.set_as::<Borsh<MyState>>(key, program_id, &state);
+//! svm.modify::<ZeroCopy<MyPodState>>(&key, |state| state.counter += 1);
+//! ```
+use std::marker::PhantomData;
+
+use solana_sdk::program_pack::{IsInitialized, Pack};
+
+/// Describes how an account's data maps to [`AccountData::Value`].
+pub trait AccountData {
+    type Value;
+
+    fn decode(data: &[u8]) -> Self::Value;
+    fn encode(value: &Self::Value) -> Vec<u8>;
+}
+
+/// An 8 byte account discriminator (i.e. as used by Anchor).
+pub trait Discriminator {
+    const DISCRIMINATOR: [u8; 8];
+}
+
+/// Accounts implementing [`Pack`], decoding fails for uninitialized
+/// accounts.
+pub struct Packed<T>(PhantomData<T>);
+
+impl<T> AccountData for Packed<T>
+where
+    T: Pack + IsInitialized,
+{
+    type Value = T;
+
+    fn decode(data: &[u8]) -> Self::Value {
+        T::unpack(data).unwrap_or_else(|err| panic!("Failed to unpack account; err={err}"))
+    }
+
+    fn encode(value: &Self::Value) -> Vec<u8> {
+        let mut buf = vec![0; T::LEN];
+        value.pack_into_slice(&mut buf);
+
+        buf
+    }
+}
+
+/// Borsh serialized accounts, trailing bytes are ignored when decoding.
+pub struct Borsh<T>(PhantomData<T>);
+
+impl<T> AccountData for Borsh<T>
+where
+    T: borsh::BorshSerialize + borsh::BorshDeserialize,
+{
+    type Value = T;
+
+    fn decode(data: &[u8]) -> Self::Value {
+        T::deserialize(&mut &data[..])
+            .unwrap_or_else(|err| panic!("Failed to deserialize account; err={err}"))
+    }
+
+    fn encode(value: &Self::Value) -> Vec<u8> {
+        borsh::to_vec(value).unwrap()
+    }
+}
+
+/// Borsh serialized accounts prefixed by [`Discriminator::DISCRIMINATOR`].
+pub struct DiscriminatedBorsh<T>(PhantomData<T>);
+
+impl<T> AccountData for DiscriminatedBorsh<T>
+where
+    T: borsh::BorshSerialize + borsh::BorshDeserialize + Discriminator,
+{
+    type Value = T;
+
+    fn decode(data: &[u8]) -> Self::Value {
+        assert_eq!(
+            data.get(..8),
+            Some(T::DISCRIMINATOR.as_slice()),
+            "Discriminator mismatch; expected={:?}",
+            T::DISCRIMINATOR
+        );
+
+        Borsh::<T>::decode(&data[8..])
+    }
+
+    fn encode(value: &Self::Value) -> Vec<u8> {
+        T::DISCRIMINATOR
+            .into_iter()
+            .chain(Borsh::<T>::encode(value))
+            .collect()
+    }
+}
+
+/// Zero-copy accounts implementing [`bytemuck::Pod`], trailing bytes are
+/// ignored when decoding.
+pub struct ZeroCopy<T>(PhantomData<T>);
+
+impl<T> AccountData for ZeroCopy<T>
+where
+    T: bytemuck::Pod,
+{
+    type Value = T;
+
+    fn decode(data: &[u8]) -> Self::Value {
+        let len = std::mem::size_of::<T>();
+        assert!(data.len() >= len, "Account too small; len={}; expected={len}", data.len());
+
+        bytemuck::pod_read_unaligned(&data[..len])
+    }
+
+    fn encode(value: &Self::Value) -> Vec<u8> {
+        bytemuck::bytes_of(value).to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_sdk::account::Account;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::rent::Rent;
+
+    use super::*;
+    use crate::svm::DefaultLoader;
+    use crate::Svm;
+
+    const OWNER: Pubkey = Pubkey::new_from_array([3; 32]);
+
+    #[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    struct Counter {
+        count: u64,
+        authority: Pubkey,
+    }
+
+    impl Discriminator for Counter {
+        const DISCRIMINATOR: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+    }
+
+    #[test]
+    fn modify_lazily_loaded() {
+        let key = Pubkey::new_unique();
+        let mut svm = Svm::new(DefaultLoader::from_iter([(
+            key,
+            Account {
+                lamports: 1,
+                data: vec![1, 0, 0, 0, 0, 0, 0, 0, 0xff],
+                owner: OWNER,
+                ..Default::default()
+            },
+        )]));
+
+        svm.modify::<ZeroCopy<u64>>(&key, |counter| *counter += 1);
+
+        let account = svm.get(&key).unwrap();
+        assert_eq!(svm.get_as::<ZeroCopy<u64>>(&key), 2);
+        assert_eq!(account.data.last(), Some(&0xff));
+        assert_eq!(account.owner, OWNER);
+        assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
+    }
+
+    #[test]
+    fn discriminated_borsh() {
+        let key = Pubkey::new_unique();
+        let mut svm: Svm = Svm::default();
+
+        svm.set_as::<DiscriminatedBorsh<Counter>>(
+            key,
+            OWNER,
+            &Counter { count: 5, authority: OWNER },
+        );
+        svm.modify::<DiscriminatedBorsh<Counter>>(&key, |counter| counter.count += 1);
+
+        assert_eq!(
+            svm.get_as::<DiscriminatedBorsh<Counter>>(&key),
+            Counter { count: 6, authority: OWNER }
+        );
+        assert_eq!(svm.get(&key).unwrap().data[..8], Counter::DISCRIMINATOR);
+    }
+}
@@ -0,0 +1,181 @@
+//! Human readable labels for pubkeys, see [`crate::Svm::render`].
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{
+    address_lookup_table, bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable,
+    compute_budget, ed25519_program, native_loader, pubkey, secp256k1_program, stake,
+    system_program, sysvar, vote,
+};
+
+static WELL_KNOWN: OnceLock<AddressBook> = OnceLock::new();
+
+/// Maps pubkeys to labels, falling back to the labels of
+/// [`crate::utils::keypair`].
+///
+/// Keys are rendered as `label (AKnL…)`, [`AddressBook::restore`] reverses
+/// [`AddressBook::substitute`].
+#[derive(Debug, Clone)]
+pub struct AddressBook {
+    labels: HashMap<Pubkey, String>,
+}
+
+impl Default for AddressBook {
+    /// An address book holding well-known programs, sysvars, mints & the
+    /// test payer.
+    fn default() -> Self {
+        AddressBook::well_known().clone()
+    }
+}
+
+impl AddressBook {
+    pub fn empty() -> Self {
+        AddressBook { labels: HashMap::default() }
+    }
+
+    pub fn well_known() -> &'static AddressBook {
+        WELL_KNOWN.get_or_init(|| {
+            let mut book = AddressBook::empty();
+            book.extend([
+                (system_program::ID, "system_program"),
+                (compute_budget::ID, "compute_budget"),
+                (address_lookup_table::program::ID, "address_lookup_table"),
+                (vote::program::ID, "vote_program"),
+                (stake::program::ID, "stake_program"),
+                (ed25519_program::ID, "ed25519_program"),
+                (secp256k1_program::ID, "secp256k1_program"),
+                (native_loader::ID, "native_loader"),
+                (bpf_loader::ID, "bpf_loader"),
+                (bpf_loader_deprecated::ID, "bpf_loader_deprecated"),
+                (bpf_loader_upgradeable::ID, "bpf_loader_upgradeable"),
+                (pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"), "spl_token"),
+                (pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"), "spl_token_2022"),
+                (
+                    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xSNgSd4S6SaAyJ1"),
+                    "spl_associated_token_account",
+                ),
+                (pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"), "spl_memo"),
+                (sysvar::clock::ID, "sysvar_clock"),
+                (sysvar::rent::ID, "sysvar_rent"),
+                (sysvar::epoch_schedule::ID, "sysvar_epoch_schedule"),
+                (sysvar::epoch_rewards::ID, "sysvar_epoch_rewards"),
+                (sysvar::instructions::ID, "sysvar_instructions"),
+                (sysvar::last_restart_slot::ID, "sysvar_last_restart_slot"),
+                (sysvar::recent_blockhashes::ID, "sysvar_recent_blockhashes"),
+                (sysvar::slot_hashes::ID, "sysvar_slot_hashes"),
+                (sysvar::stake_history::ID, "sysvar_stake_history"),
+                (pubkey!("So11111111111111111111111111111111111111112"), "wSOL"),
+                (pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"), "USDC"),
+                (pubkey!("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"), "USDT"),
+                (crate::utils::TEST_PAYER, "test_payer"),
+            ]);
+
+            book
+        })
+    }
+
+    pub fn insert(&mut self, key: Pubkey, label: &str) {
+        self.labels.insert(key, label.to_owned());
+    }
+
+    /// Inserts each label, i.e. from [`crate::Scenario::labels`].
+    pub fn extend<S>(&mut self, labels: impl IntoIterator<Item = (Pubkey, S)>)
+    where
+        S: AsRef<str>,
+    {
+        for (key, label) in labels {
+            self.insert(key, label.as_ref());
+        }
+    }
+
+    pub fn label(&self, key: &Pubkey) -> Option<String> {
+        self.labels
+            .get(key)
+            .cloned()
+            .or_else(|| crate::utils::label(key))
+    }
+
+    /// The key labelled `label`, if any.
+    pub fn key(&self, label: &str) -> Option<Pubkey> {
+        self.entries()
+            .into_iter()
+            .find(|(_, other)| other == label)
+            .map(|(key, _)| key)
+    }
+
+    /// Renders `key` as `label (AKnL…)` if labelled, else as base58.
+    pub fn render(&self, key: &Pubkey) -> String {
+        match self.label(key) {
+            Some(label) => format!("{label} ({}…)", &key.to_string()[..4]),
+            None => key.to_string(),
+        }
+    }
+
+    /// Replaces every labelled base58 pubkey in `text` with its rendering.
+    pub fn substitute(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(is_base58) {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find(|c| !is_base58(c)).unwrap_or(rest.len());
+
+            match rest[..end].parse::<Pubkey>() {
+                Ok(key) if (32..=44).contains(&end) => out.push_str(&self.render(&key)),
+                _ => out.push_str(&rest[..end]),
+            }
+            rest = &rest[end..];
+        }
+        out.push_str(rest);
+
+        out
+    }
+
+    /// Reverses [`Self::substitute`], replacing rendered labels with their
+    /// base58 pubkeys.
+    pub fn restore(&self, text: &str) -> String {
+        self.entries()
+            .into_iter()
+            .fold(text.to_owned(), |text, (key, _)| {
+                text.replace(&self.render(&key), &key.to_string())
+            })
+    }
+
+    /// All labelled keys, including [`crate::utils::keypair`] labels.
+    fn entries(&self) -> Vec<(Pubkey, String)> {
+        crate::utils::labels()
+            .into_iter()
+            .filter(|(key, _)| !self.labels.contains_key(key))
+            .chain(self.labels.iter().map(|(key, label)| (*key, label.clone())))
+            .collect()
+    }
+}
+
+fn is_base58(c: char) -> bool {
+    c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitute_round_trip() {
+        let mut book = AddressBook::default();
+        let vault = Pubkey::new_unique();
+        book.insert(vault, "vault");
+
+        let text = format!("Program {} invoke [1]; vault={vault}; payer=8", system_program::ID);
+        let substituted = book.substitute(&text);
+        assert_eq!(
+            substituted,
+            format!(
+                "Program system_program (1111…) invoke [1]; vault=vault ({}…); payer=8",
+                &vault.to_string()[..4]
+            )
+        );
+        assert_eq!(book.restore(&substituted), text);
+        assert_eq!(book.key("vault"), Some(vault));
+    }
+}
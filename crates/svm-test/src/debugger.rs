@@ -0,0 +1,118 @@
+//! Runs SBF programs under rbpf's GDB remote stub, see
+//! [`crate::Svm::debug_program`].
+//!
+//! The debugged program is swapped for a builtin which executes the ELF in the
+//! interpreter with [`solana_rbpf::vm::EbpfVm::debug_port`] set, each
+//! invocation blocks until a debugger connects (`target remote :<port>`).
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use solana_bpf_loader_program::create_vm;
+use solana_bpf_loader_program::serialization::{deserialize_parameters, serialize_parameters};
+use solana_bpf_loader_program::syscalls::create_program_runtime_environment_v1;
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::{declare_process_instruction, ic_msg};
+use solana_rbpf::elf::Executable;
+use solana_rbpf::error::{EbpfError, StableResult};
+use solana_rbpf::verifier::RequisiteVerifier;
+use solana_sdk::entrypoint::SUCCESS;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+
+static PROGRAMS: OnceLock<RwLock<HashMap<Pubkey, (Arc<Vec<u8>>, u16)>>> = OnceLock::new();
+
+/// Registers `elf` as the implementation of `program_id`, debugged on `port`.
+pub(crate) fn register(program_id: Pubkey, elf: Vec<u8>, port: u16) {
+    PROGRAMS
+        .get_or_init(Default::default)
+        .write()
+        .unwrap()
+        .insert(program_id, (Arc::new(elf), port));
+}
+
+declare_process_instruction!(DebugProcessor, 0, |invoke_context| {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let program_id = *instruction_context.get_last_program_key(transaction_context)?;
+    let (elf, port) = PROGRAMS
+        .get()
+        .and_then(|programs| programs.read().unwrap().get(&program_id).cloned())
+        .ok_or(InstructionError::UnsupportedProgramId)?;
+
+    execute(invoke_context, program_id, &elf, port)
+});
+
+/// Mirrors the bpf loader's `execute`, interpreted & with the debug port set.
+fn execute<'a>(
+    invoke_context: &mut InvokeContext<'a>,
+    program_id: Pubkey,
+    elf: &[u8],
+    port: u16,
+) -> Result<(), InstructionError> {
+    let environment = create_program_runtime_environment_v1(
+        &invoke_context.feature_set,
+        invoke_context.get_compute_budget(),
+        false,
+        true,
+    )
+    .map_err(|err| {
+        ic_msg!(invoke_context, "Failed to create program runtime: {}", err);
+        InstructionError::ProgramEnvironmentSetupFailure
+    })?;
+    let executable =
+        Executable::<InvokeContext<'a>>::load(elf, Arc::new(environment)).map_err(|err| {
+            ic_msg!(invoke_context, "Failed to load ELF: {}", err);
+            InstructionError::InvalidAccountData
+        })?;
+    executable.verify::<RequisiteVerifier>().map_err(|err| {
+        ic_msg!(invoke_context, "Failed to verify ELF: {}", err);
+        InstructionError::InvalidAccountData
+    })?;
+
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let (parameter_bytes, regions, accounts_metadata) =
+        serialize_parameters(transaction_context, instruction_context, true)?;
+
+    // NB: Printed unconditionally, the test blocks until a debugger attaches.
+    eprintln!("Waiting for debugger; program={program_id}; port={port}");
+    let compute_meter_prev = invoke_context.get_remaining();
+    let (compute_units_consumed, result) = {
+        create_vm!(vm, &executable, regions, accounts_metadata.clone(), invoke_context);
+        let mut vm = vm.map_err(|_| InstructionError::ProgramEnvironmentSetupFailure)?;
+        vm.debug_port = Some(port);
+
+        vm.execute_program(&executable, true)
+    };
+    ic_msg!(
+        invoke_context,
+        "Program {} consumed {} of {} compute units",
+        program_id,
+        compute_units_consumed,
+        compute_meter_prev
+    );
+
+    match result {
+        StableResult::Ok(SUCCESS) => {}
+        StableResult::Ok(status) => return Err(InstructionError::from(status)),
+        StableResult::Err(EbpfError::SyscallError(err)) => {
+            return Err(err
+                .downcast::<InstructionError>()
+                .map_or(InstructionError::ProgramFailedToComplete, |err| *err))
+        }
+        StableResult::Err(err) => {
+            ic_msg!(invoke_context, "Program failed to complete: {}", err);
+            return Err(InstructionError::ProgramFailedToComplete);
+        }
+    }
+
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    deserialize_parameters(
+        transaction_context,
+        instruction_context,
+        true,
+        parameter_bytes.as_slice(),
+        &accounts_metadata,
+    )
+}
@@ -0,0 +1,269 @@
+//! Differential testing, running the same transaction against two program
+//! implementations from identical state & comparing the [`Outcome`]s.
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_sdk::transaction_context::TransactionReturnData;
+
+use crate::svm::{FailedTransactionMetadata, TransactionMetadata};
+use crate::{AccountLoader, Svm};
+
+/// The observable effects of a transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub err: Option<TransactionError>,
+    /// Program logs, excluding `consumed .. compute units` lines.
+    pub logs: Vec<String>,
+    pub return_data: TransactionReturnData,
+    pub compute_units: u64,
+    /// The post state of the transaction's writable accounts.
+    pub accounts: Vec<(Pubkey, Account)>,
+}
+
+impl Outcome {
+    pub fn new(
+        result: &Result<TransactionMetadata, FailedTransactionMetadata>,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Self {
+        let (err, meta) = match result {
+            Ok(meta) => (None, meta),
+            Err(FailedTransactionMetadata { err, meta }) => (Some(err.clone()), meta),
+        };
+
+        Outcome {
+            err,
+            logs: meta
+                .logs
+                .iter()
+                .filter(|log| !is_compute_units_log(log))
+                .cloned()
+                .collect(),
+            return_data: meta.return_data.clone(),
+            compute_units: meta.compute_units_consumed,
+            accounts,
+        }
+    }
+
+    /// Compares `self` (left) against `other` (right).
+    pub fn diff(&self, other: &Outcome) -> Vec<Mismatch> {
+        let mut mismatches = Vec::default();
+        if self.err != other.err {
+            mismatches.push(Mismatch::Result { left: self.err.clone(), right: other.err.clone() });
+        }
+        if self.compute_units != other.compute_units {
+            mismatches.push(Mismatch::ComputeUnits {
+                left: self.compute_units,
+                right: other.compute_units,
+            });
+        }
+        if self.logs != other.logs {
+            mismatches.push(Mismatch::Logs { left: self.logs.clone(), right: other.logs.clone() });
+        }
+        if self.return_data != other.return_data {
+            mismatches.push(Mismatch::ReturnData {
+                left: self.return_data.clone(),
+                right: other.return_data.clone(),
+            });
+        }
+        for (key, left) in &self.accounts {
+            let right = other
+                .accounts
+                .iter()
+                .find(|(other, _)| other == key)
+                .map(|(_, account)| account.clone())
+                .unwrap_or_default();
+            if *left != right {
+                mismatches.push(Mismatch::Account { key: *key, left: left.clone(), right });
+            }
+        }
+
+        mismatches
+    }
+}
+
+/// A difference between two [`Outcome`]s.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    Result {
+        left: Option<TransactionError>,
+        right: Option<TransactionError>,
+    },
+    ComputeUnits {
+        left: u64,
+        right: u64,
+    },
+    Logs {
+        left: Vec<String>,
+        right: Vec<String>,
+    },
+    ReturnData {
+        left: TransactionReturnData,
+        right: TransactionReturnData,
+    },
+    Account {
+        key: Pubkey,
+        left: Account,
+        right: Account,
+    },
+    /// Post lamports, see [`crate::replay::Replay::execute`].
+    Balance {
+        key: Pubkey,
+        left: u64,
+        right: u64,
+    },
+    /// Post token amounts, `None` if the account has no token balance, see
+    /// [`crate::replay::Replay::execute`].
+    TokenBalance {
+        key: Pubkey,
+        left: Option<u64>,
+        right: Option<u64>,
+    },
+}
+
+/// Replays transactions against two builds of a program, i.e. the deployed
+/// ELF (see [`crate::utils::program_elf`]) & a local build.
+pub struct ProgramDiff {
+    program_id: Pubkey,
+    left: Vec<u8>,
+    right: Vec<u8>,
+    allowed: Vec<Box<dyn Fn(&Mismatch) -> bool>>,
+}
+
+/// The mismatches of a single transaction replayed by [`ProgramDiff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub signature: Signature,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl ProgramDiff {
+    pub fn new(program_id: Pubkey, left: Vec<u8>, right: Vec<u8>) -> Self {
+        ProgramDiff { program_id, left, right, allowed: Vec::default() }
+    }
+
+    /// Ignores mismatches matching `allowed`.
+    pub fn allow(mut self, allowed: impl Fn(&Mismatch) -> bool + 'static) -> Self {
+        self.allowed.push(Box::new(allowed));
+
+        self
+    }
+
+    pub fn allow_compute_units(self) -> Self {
+        self.allow(|mismatch| matches!(mismatch, Mismatch::ComputeUnits { .. }))
+    }
+
+    pub fn allow_account(self, key: Pubkey) -> Self {
+        self.allow(move |mismatch| match mismatch {
+            Mismatch::Account { key: other, .. } => *other == key,
+            _ => false,
+        })
+    }
+
+    /// Ignores log lines containing `pattern`, i.e. a version string.
+    pub fn allow_log(self, pattern: &str) -> Self {
+        let pattern = pattern.to_owned();
+        let strip = move |logs: &[String]| -> Vec<String> {
+            logs.iter()
+                .filter(|log| !log.contains(&pattern))
+                .cloned()
+                .collect()
+        };
+
+        self.allow(move |mismatch| match mismatch {
+            Mismatch::Logs { left, right } => strip(left) == strip(right),
+            _ => false,
+        })
+    }
+
+    /// Replays `txs` in order against each build from the current state of
+    /// `svm`, returning the transactions that diverged.
+    ///
+    /// `svm` is left in its original state.
+    pub fn run<L>(
+        &self,
+        svm: &mut Svm<L>,
+        txs: impl IntoIterator<Item = impl Into<VersionedTransaction>>,
+    ) -> Vec<Divergence>
+    where
+        L: AccountLoader,
+    {
+        let txs: Vec<VersionedTransaction> = txs.into_iter().map(Into::into).collect();
+        let checkpoint = svm.checkpoint();
+        let mut replay = |elf: &[u8]| -> Vec<Outcome> {
+            svm.restore(&checkpoint);
+            match svm.get_or_load(&self.program_id).owner {
+                bpf_loader_upgradeable::ID => svm.override_program_elf(self.program_id, elf),
+                _ => svm.add_program(self.program_id, elf),
+            }
+
+            txs.iter()
+                .map(|tx| svm.execute_transaction_with_outcome(tx.clone()).1)
+                .collect()
+        };
+        let left = replay(&self.left);
+        let right = replay(&self.right);
+        svm.restore(&checkpoint);
+
+        txs.iter()
+            .zip(left.iter().zip(&right))
+            .map(|(tx, (left, right))| Divergence {
+                signature: tx.signatures[0],
+                mismatches: left
+                    .diff(right)
+                    .into_iter()
+                    .filter(|mismatch| !self.allowed.iter().any(|allowed| allowed(mismatch)))
+                    .collect(),
+            })
+            .filter(|divergence| !divergence.mismatches.is_empty())
+            .collect()
+    }
+}
+
+pub(crate) fn is_compute_units_log(log: &str) -> bool {
+    log.starts_with("Program ") && log.contains(" consumed ") && log.ends_with(" compute units")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_ignores_compute_unit_logs() {
+        let key = Pubkey::new_unique();
+        let log = "Program log: hello".to_owned();
+        let left = Outcome::new(
+            &Ok(TransactionMetadata {
+                logs: vec![log.clone()],
+                compute_units_consumed: 100,
+                ..Default::default()
+            }),
+            vec![(key, Account { lamports: 1, ..Default::default() })],
+        );
+        let right = Outcome::new(
+            &Ok(TransactionMetadata {
+                logs: vec![
+                    log,
+                    "Program 11111111111111111111111111111111 consumed 150 of 200000 compute units"
+                        .to_owned(),
+                ],
+                compute_units_consumed: 150,
+                ..Default::default()
+            }),
+            vec![(key, Account { lamports: 2, ..Default::default() })],
+        );
+
+        assert_eq!(
+            left.diff(&right),
+            vec![
+                Mismatch::ComputeUnits { left: 100, right: 150 },
+                Mismatch::Account {
+                    key,
+                    left: Account { lamports: 1, ..Default::default() },
+                    right: Account { lamports: 2, ..Default::default() },
+                },
+            ]
+        );
+    }
+}
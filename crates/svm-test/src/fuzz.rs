@@ -0,0 +1,276 @@
+//! Structure-aware fuzzing for programs running under [`Svm`].
+//!
+//! A [`Fuzzer`] is driven by raw bytes (i.e. from `cargo fuzz`/libFuzzer) and
+//! turns them into a sequence of instructions via a user supplied generator.
+//! Each instruction is executed in its own transaction (paid for by
+//! [`TEST_PAYER`]) and all registered invariants are checked after every
+//! transaction.
+//!
+//! Setup runs once, every iteration then starts by restoring a [`Checkpoint`]
+//! of the prepared state.
+//!
+//! ```ignore
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| FUZZER.with(|fuzzer| fuzzer.run(data)));
+//! ```
+use std::cell::{RefCell, RefMut};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+
+use arbitrary::Unstructured;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::transaction::Transaction;
+
+use crate::svm::Checkpoint;
+use crate::utils::{read_json, test_payer_keypair, TEST_PAYER};
+use crate::{AccountLoader, Svm};
+
+type Invariant<L> = Box<dyn Fn(&Svm<L>) -> Result<(), String>>;
+
+pub struct Fuzzer<L, S, G> {
+    setup: S,
+    generate: G,
+    invariants: Vec<(&'static str, Invariant<L>)>,
+    max_instructions: usize,
+    crash_dir: PathBuf,
+    prepared: RefCell<Option<(Svm<L>, Checkpoint)>>,
+}
+
+/// A sequence of instructions that violated an invariant (or panicked).
+#[derive(Debug)]
+pub struct Failure {
+    pub invariant: String,
+    pub reason: String,
+    pub instructions: Vec<Instruction>,
+}
+
+impl<L, S, G> Fuzzer<L, S, G>
+where
+    L: AccountLoader,
+    S: Fn() -> Svm<L>,
+    G: Fn(&mut Unstructured, &Svm<L>) -> arbitrary::Result<Instruction>,
+{
+    /// Creates a new fuzzer.
+    ///
+    /// `setup` must return a fully prepared [`Svm`] (programs, accounts & a
+    /// funded [`TEST_PAYER`]), `generate` is called once per instruction with
+    /// the current state of the [`Svm`].
+    pub fn new(setup: S, generate: G) -> Self {
+        Fuzzer {
+            setup,
+            generate,
+            invariants: Vec::default(),
+            max_instructions: 16,
+            crash_dir: PathBuf::from("fuzz/crashes"),
+            prepared: RefCell::default(),
+        }
+    }
+
+    pub fn with_invariant(
+        mut self,
+        name: &'static str,
+        invariant: impl Fn(&Svm<L>) -> Result<(), String> + 'static,
+    ) -> Self {
+        self.invariants.push((name, Box::new(invariant)));
+
+        self
+    }
+
+    pub fn with_max_instructions(mut self, max_instructions: usize) -> Self {
+        self.max_instructions = max_instructions;
+
+        self
+    }
+
+    pub fn with_crash_dir(mut self, crash_dir: impl Into<PathBuf>) -> Self {
+        self.crash_dir = crash_dir.into();
+
+        self
+    }
+
+    /// Runs a single fuzz iteration.
+    ///
+    /// On failure the sequence is minimized, written to the crash directory &
+    /// the fuzzer panics so libFuzzer records the input.
+    pub fn run(&self, data: &[u8]) {
+        let Err(failure) = self.generate_and_check(data) else {
+            return;
+        };
+
+        let failure = self.minimize(failure);
+        let path = self.save(&failure);
+
+        panic!(
+            "Invariant violated; invariant={}; reason={}; instructions={}; crash={path:?}",
+            failure.invariant,
+            failure.reason,
+            failure.instructions.len(),
+        );
+    }
+
+    /// Replays a sequence previously written by [`Self::run`].
+    pub fn replay(&self, path: &Path) -> Result<(), Failure> {
+        self.check(read_json(path))
+    }
+
+    /// Executes `instructions` from a fresh setup and returns the first
+    /// failure, if any.
+    pub fn check(&self, instructions: Vec<Instruction>) -> Result<(), Failure> {
+        let mut svm = self.fresh();
+        for (i, ix) in instructions.iter().enumerate() {
+            if let Err((invariant, reason)) = self.step(&mut svm, ix.clone()) {
+                let mut instructions = instructions;
+                instructions.truncate(i + 1);
+
+                return Err(Failure { invariant, reason, instructions });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Shrinks a failing sequence by greedily removing instructions while the
+    /// sequence still fails.
+    pub fn minimize(&self, mut failure: Failure) -> Failure {
+        let mut i = failure.instructions.len();
+        while i > 0 {
+            i -= 1;
+
+            let mut candidate = failure.instructions.clone();
+            candidate.remove(i);
+            if let Err(smaller) = self.check(candidate) {
+                failure = smaller;
+                i = i.min(failure.instructions.len());
+            }
+        }
+
+        failure
+    }
+
+    fn generate_and_check(&self, data: &[u8]) -> Result<(), Failure> {
+        let mut svm = self.fresh();
+        let mut u = Unstructured::new(data);
+        let mut instructions = Vec::default();
+
+        while !u.is_empty() && instructions.len() < self.max_instructions {
+            let Ok(ix) = (self.generate)(&mut u, &svm) else {
+                break;
+            };
+            instructions.push(ix.clone());
+
+            if let Err((invariant, reason)) = self.step(&mut svm, ix) {
+                return Err(Failure { invariant, reason, instructions });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the prepared [`Svm`] rewound to its post setup state.
+    fn fresh(&self) -> RefMut<Svm<L>> {
+        RefMut::map(self.prepared.borrow_mut(), |prepared| {
+            let (svm, checkpoint) = prepared.get_or_insert_with(|| {
+                let svm = (self.setup)();
+                let checkpoint = svm.checkpoint();
+
+                (svm, checkpoint)
+            });
+            svm.restore(checkpoint);
+
+            svm
+        })
+    }
+
+    fn step(&self, svm: &mut Svm<L>, ix: Instruction) -> Result<(), (String, String)> {
+        std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let tx = Transaction::new_signed_with_payer(
+                &[ix],
+                Some(&TEST_PAYER),
+                &[test_payer_keypair()],
+                svm.blockhash(),
+            );
+
+            // Failed transactions are expected, only invariants matter.
+            let _ = svm.execute_transaction(tx);
+            svm.expire_blockhash();
+        }))
+        .map_err(|err| ("panic".to_owned(), svm.address_book.substitute(&panic_message(err))))?;
+
+        self.invariants.iter().try_for_each(|(name, invariant)| {
+            invariant(svm)
+                .map_err(|reason| (name.to_string(), svm.address_book.substitute(&reason)))
+        })
+    }
+
+    fn save(&self, failure: &Failure) -> PathBuf {
+        std::fs::create_dir_all(&self.crash_dir).unwrap_or_else(|err| {
+            panic!("Failed to create crash dir; path={:?}; err={err}", self.crash_dir)
+        });
+
+        let serialized = serde_json::to_vec_pretty(&failure.instructions).unwrap();
+        let hash = solana_sdk::hash::hash(&serialized);
+        let path = self
+            .crash_dir
+            .join(format!("{}-{hash}.json", failure.invariant));
+        std::fs::write(&path, serialized)
+            .unwrap_or_else(|err| panic!("Failed to write crash; path={path:?}; err={err}"));
+
+        path
+    }
+}
+
+fn panic_message(err: Box<dyn std::any::Any + Send>) -> String {
+    err.downcast_ref::<&str>()
+        .map(|msg| msg.to_string())
+        .or_else(|| err.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::account::Account;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::system_instruction;
+
+    use super::*;
+    use crate::svm::DefaultLoader;
+
+    const RECIPIENT: Pubkey = Pubkey::new_from_array([2; 32]);
+
+    #[test]
+    fn minimize() {
+        let fuzzer = Fuzzer::new(
+            || {
+                let mut svm: Svm<DefaultLoader> = Svm::default();
+                svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+
+                svm
+            },
+            |u: &mut Unstructured, _: &Svm<DefaultLoader>| {
+                let lamports = u.int_in_range(1..=10u64.pow(6))?;
+
+                Ok(system_instruction::transfer(&TEST_PAYER, &RECIPIENT, lamports))
+            },
+        )
+        .with_invariant("recipient_below_threshold", |svm| {
+            match svm
+                .get(&RECIPIENT)
+                .map(|account| account.lamports)
+                .unwrap_or_default()
+            {
+                lamports if lamports >= 10u64.pow(6) => Err(format!("lamports={lamports}")),
+                _ => Ok(()),
+            }
+        });
+
+        // Four transfers, only the last crosses the threshold on its own.
+        let instructions = [10, 20, 30, 10u64.pow(6)]
+            .map(|lamports| system_instruction::transfer(&TEST_PAYER, &RECIPIENT, lamports))
+            .to_vec();
+        let failure = fuzzer.check(instructions).unwrap_err();
+        assert_eq!(failure.invariant, "recipient_below_threshold");
+
+        let minimized = fuzzer.minimize(failure);
+        assert_eq!(minimized.instructions.len(), 1);
+        assert_eq!(minimized.invariant, "recipient_below_threshold");
+    }
+}
@@ -1,10 +1,22 @@
 use std::collections::BTreeMap;
+#[cfg(feature = "transaction-status")]
+use std::path::Path;
 use std::sync::{Arc, OnceLock, Weak};
 
 use dashmap::DashMap;
+#[cfg(feature = "transaction-status")]
+use replay::Replay;
+#[cfg(feature = "transaction-status")]
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
+#[cfg(feature = "transaction-status")]
+use solana_sdk::signature::Signature;
 use test_rpc::TestRpc;
+#[cfg(feature = "transaction-status")]
+use test_rpc::{test_data_path, test_rpc_url};
+#[cfg(feature = "transaction-status")]
+use utils::{read_json, try_write_json};
 
 use super::*;
 
@@ -67,6 +79,48 @@ impl Harness {
         Arc::new(Scenario { runtime: &self.runtime, rpc })
     }
 
+    /// Replays the historical transaction `signature`, see [`Replay`].
+    ///
+    /// With `TEST_RPC` set the transaction & every account it touched are
+    /// recorded as the scenario `replay/{signature}`.
+    #[cfg(feature = "transaction-status")]
+    pub fn replay_transaction(&'static self, signature: &Signature) -> Replay<Arc<Scenario>> {
+        self.replay_transaction_with(signature, test_rpc_url().as_deref(), &test_data_path())
+    }
+
+    /// Replays `signature` as [`Self::replay_transaction`], recording from
+    /// `url` (if set) into `dir` rather than `TEST_RPC` & `TEST_DATA`.
+    #[cfg(feature = "transaction-status")]
+    pub fn replay_transaction_with(
+        &'static self,
+        signature: &Signature,
+        url: Option<&str>,
+        dir: &Path,
+    ) -> Replay<Arc<Scenario>> {
+        let name = format!("replay/{signature}");
+        let path = dir.join(format!("{name}.tx.json"));
+        let scenario = || {
+            Arc::new(Scenario {
+                runtime: &self.runtime,
+                rpc: TestRpc::load_scenario_from(dir, &name, url),
+            })
+        };
+        let Some(url) = url else {
+            return Replay::new(read_json(&path), scenario());
+        };
+
+        let recorded = self
+            .runtime
+            .block_on(replay::fetch_transaction(&RpcClient::new(url.to_owned()), signature));
+        std::fs::create_dir_all(path.parent().unwrap())
+            .unwrap_or_else(|err| panic!("Failed to create replay dir; path={path:?}; err={err}"));
+        try_write_json(&path, &recorded);
+        let replay = Replay::new(recorded, scenario());
+        replay.preload();
+
+        replay
+    }
+
     fn load_scenario(&'static self, name: &str) -> Arc<Scenario> {
         let rpc = TestRpc::load_scenario(name);
 
@@ -87,6 +141,19 @@ impl Scenario {
     ) -> ScenarioWithOverrides {
         ScenarioWithOverrides { scenario: self.clone(), overrides }
     }
+
+    /// The [`crate::utils::executable_hash`] of every program account recorded
+    /// by this scenario.
+    pub fn program_hashes(&self) -> BTreeMap<Pubkey, String> {
+        self.rpc.program_hashes()
+    }
+
+    /// Labels read from `test-data/{name}.labels.json`, added to the
+    /// [`crate::Svm::address_book`] of any [`crate::Svm`] built from this
+    /// scenario.
+    pub fn labels(&self) -> BTreeMap<Pubkey, String> {
+        self.rpc.labels()
+    }
 }
 
 #[async_trait::async_trait]
@@ -100,6 +167,10 @@ impl AccountLoader for Scenario {
     fn load(&self, key: &Pubkey) -> Account {
         self.rpc.account_sync(self.runtime, key)
     }
+
+    fn labels(&self) -> BTreeMap<Pubkey, String> {
+        Scenario::labels(self)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -133,4 +204,8 @@ impl AccountLoader for ScenarioWithOverrides {
 
         self.scenario.load(key)
     }
+
+    fn labels(&self) -> BTreeMap<Pubkey, String> {
+        self.scenario.labels()
+    }
 }
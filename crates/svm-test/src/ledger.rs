@@ -0,0 +1,259 @@
+//! The history of transactions executed by an [`crate::Svm`].
+use std::ops::RangeBounds;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use solana_sdk::clock::{Slot, UnixTimestamp};
+use solana_sdk::message::v0::LoadedAddresses;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+
+use crate::svm::TransactionMetadata;
+#[cfg(all(feature = "spl", feature = "transaction-status"))]
+use crate::token_balances::TokenBalance;
+
+/// A single processed transaction, successful or not.
+#[derive(Debug, Clone)]
+pub struct LedgerEntry {
+    pub signature: Signature,
+    pub slot: Slot,
+    /// The [`solana_sdk::clock::Clock`] timestamp at execution.
+    pub block_time: UnixTimestamp,
+    pub transaction: VersionedTransaction,
+    /// Static & lookup table keys, in message order.
+    pub account_keys: Vec<Pubkey>,
+    pub loaded_addresses: LoadedAddresses,
+    pub meta: TransactionMetadata,
+    pub err: Option<TransactionError>,
+    /// The fee charged (including any priority fee), lamports are conserved
+    /// so this is the drop in the total balance of the transaction's accounts.
+    pub fee: u64,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    #[cfg(all(feature = "spl", feature = "transaction-status"))]
+    pub pre_token_balances: Vec<TokenBalance>,
+    #[cfg(all(feature = "spl", feature = "transaction-status"))]
+    pub post_token_balances: Vec<TokenBalance>,
+}
+
+impl LedgerEntry {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "signature": self.signature.to_string(),
+            "slot": self.slot,
+            "blockTime": self.block_time,
+            "transaction": [
+                BASE64_STANDARD.encode(bincode::serialize(&self.transaction).unwrap()),
+                "base64",
+            ],
+            "accountKeys": self.account_keys.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+            "err": self.err,
+            "fee": self.fee,
+            "preBalances": self.pre_balances,
+            "postBalances": self.post_balances,
+            "logMessages": self.meta.logs,
+            "computeUnitsConsumed": self.meta.compute_units_consumed,
+            "returnData": {
+                "programId": self.meta.return_data.program_id.to_string(),
+                "data": [BASE64_STANDARD.encode(&self.meta.return_data.data), "base64"],
+            },
+        })
+    }
+}
+
+/// Processed transactions (see [`is_processed`]) in execution order.
+#[derive(Debug, Clone, Default)]
+pub struct Ledger(Vec<LedgerEntry>);
+
+impl Ledger {
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.0
+    }
+
+    pub fn get(&self, signature: &Signature) -> Option<&LedgerEntry> {
+        self.0
+            .iter()
+            .rev()
+            .find(|entry| &entry.signature == signature)
+    }
+
+    /// Transactions touching `key`, newest first (as
+    /// `getSignaturesForAddress`).
+    pub fn for_address(&self, key: &Pubkey) -> Vec<&LedgerEntry> {
+        self.0
+            .iter()
+            .rev()
+            .filter(|entry| entry.account_keys.contains(key))
+            .collect()
+    }
+
+    /// Transactions executed within `slots`, in execution order.
+    pub fn slots(&self, slots: impl RangeBounds<Slot>) -> Vec<&LedgerEntry> {
+        self.0
+            .iter()
+            .filter(|entry| slots.contains(&entry.slot))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        self.0.iter().map(LedgerEntry::to_json).collect()
+    }
+
+    pub(crate) fn push(&mut self, entry: LedgerEntry) {
+        self.0.push(entry);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Whether a transaction failing with `err` was processed (i.e. made it into
+/// a block), as opposed to being rejected before execution.
+pub(crate) fn is_processed(err: &TransactionError) -> bool {
+    !matches!(
+        err,
+        TransactionError::AccountInUse
+            | TransactionError::AccountLoadedTwice
+            | TransactionError::AccountNotFound
+            | TransactionError::InsufficientFundsForFee
+            | TransactionError::InvalidAccountForFee
+            | TransactionError::AlreadyProcessed
+            | TransactionError::BlockhashNotFound
+            | TransactionError::MissingSignatureForFee
+            | TransactionError::SignatureFailure
+            | TransactionError::SanitizeFailure
+            | TransactionError::ClusterMaintenance
+            | TransactionError::UnsupportedVersion
+            | TransactionError::TooManyAccountLocks
+            | TransactionError::ResanitizationNeeded
+            | TransactionError::AddressLookupTableNotFound
+            | TransactionError::InvalidAddressLookupTableOwner
+            | TransactionError::InvalidAddressLookupTableData
+            | TransactionError::InvalidAddressLookupTableIndex
+            | TransactionError::WouldExceedMaxBlockCostLimit
+            | TransactionError::WouldExceedMaxAccountCostLimit
+            | TransactionError::WouldExceedMaxVoteCostLimit
+            | TransactionError::WouldExceedAccountDataBlockLimit
+            | TransactionError::WouldExceedAccountDataTotalLimit
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::account::Account;
+    use solana_sdk::clock::Clock;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::Transaction;
+
+    use super::*;
+    use crate::svm::DefaultLoader;
+    use crate::utils::{test_payer_keypair, TEST_PAYER};
+    use crate::Svm;
+
+    #[test]
+    fn query() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+
+        let transfer = |svm: &mut Svm<DefaultLoader>, to: Pubkey, slot: Slot| {
+            let mut clock = svm.get_sysvar::<Clock>();
+            clock.slot = slot;
+            svm.set_sysvar(&clock);
+
+            let tx = Transaction::new_signed_with_payer(
+                &[system_instruction::transfer(&TEST_PAYER, &to, 10u64.pow(6))],
+                Some(&TEST_PAYER),
+                &[test_payer_keypair()],
+                svm.blockhash(),
+            );
+            svm.execute_transaction(tx).unwrap().signature
+        };
+        let first = transfer(&mut svm, alice, 10);
+        let second = transfer(&mut svm, bob, 20);
+
+        let ledger = svm.ledger();
+        let entry = ledger.get(&first).unwrap();
+        assert_eq!(entry.slot, 10);
+        assert_eq!(entry.account_keys[1], alice);
+        assert_eq!(entry.post_balances[1] - entry.pre_balances[1], 10u64.pow(6));
+        assert_eq!(
+            ledger
+                .for_address(&TEST_PAYER)
+                .iter()
+                .map(|entry| entry.signature)
+                .collect::<Vec<_>>(),
+            [second, first]
+        );
+        assert_eq!(ledger.slots(15..).len(), 1);
+        assert_eq!(ledger.to_json()[1]["signature"], second.to_string());
+    }
+
+    #[test]
+    fn processed_only() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        let transfer = |svm: &Svm<DefaultLoader>, lamports: u64, blockhash: Hash| {
+            Transaction::new_signed_with_payer(
+                &[system_instruction::transfer(&TEST_PAYER, &Pubkey::new_unique(), lamports)],
+                Some(&TEST_PAYER),
+                &[test_payer_keypair()],
+                blockhash,
+            )
+        };
+
+        // Duplicates & unknown blockhashes are rejected before execution.
+        let tx = transfer(&svm, 10u64.pow(6), svm.blockhash());
+        svm.execute_transaction(tx.clone()).unwrap();
+        let err = svm.execute_transaction(tx).unwrap_err().err;
+        assert_eq!(err, TransactionError::AlreadyProcessed);
+        let err = svm
+            .execute_transaction(transfer(&svm, 10u64.pow(6), Hash::new_unique()))
+            .unwrap_err()
+            .err;
+        assert_eq!(err, TransactionError::BlockhashNotFound);
+        assert_eq!(svm.ledger().entries().len(), 1);
+
+        // Failed transactions are charged & recorded.
+        let checkpoint = svm.checkpoint();
+        let failed = transfer(&svm, 10u64.pow(10), svm.blockhash());
+        svm.execute_transaction(failed.clone()).unwrap_err();
+        let entry = svm.ledger().get(&failed.signatures[0]).unwrap();
+        assert!(entry.err.is_some());
+        assert!(entry.post_balances[0] < entry.pre_balances[0]);
+
+        // Restoring drops entries recorded after the checkpoint.
+        svm.restore(&checkpoint);
+        assert_eq!(svm.ledger().entries().len(), 1);
+        assert!(svm.ledger().get(&failed.signatures[0]).is_none());
+    }
+
+    #[test]
+    fn differential_keeps_left_entry() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        let bob = Pubkey::new_unique();
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        let tx = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&TEST_PAYER, &bob, 10u64.pow(6))],
+            Some(&TEST_PAYER),
+            &[test_payer_keypair()],
+            svm.blockhash(),
+        );
+
+        // The right run starts with bob funded.
+        let (result, mismatches) = svm.execute_transaction_differential(tx.clone(), |svm| {
+            svm.set(bob, Account { lamports: 10u64.pow(9), ..Default::default() });
+        });
+        result.unwrap();
+        assert!(!mismatches.is_empty());
+
+        // Only the left run is recorded.
+        assert_eq!(svm.ledger().entries().len(), 1);
+        let entry = svm.ledger().get(&tx.signatures[0]).unwrap();
+        assert_eq!(entry.pre_balances[1], 0);
+        assert_eq!(entry.post_balances[1], 10u64.pow(6));
+    }
+}
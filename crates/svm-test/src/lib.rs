@@ -1,10 +1,28 @@
+pub mod account_data;
+pub mod address_book;
+#[cfg(feature = "debugger")]
+pub mod debugger;
+pub mod differential;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
 mod harness;
+pub mod ledger;
+#[cfg(feature = "native")]
+pub mod native;
+#[cfg(feature = "transaction-status")]
+pub mod replay;
 pub mod ser;
 #[cfg(feature = "spl")]
 pub mod spl;
+#[cfg(feature = "proptest")]
+pub mod strategies;
 pub mod svm;
 pub mod test_rpc;
+#[cfg(feature = "spl")]
+pub mod token_balances;
 mod traits;
+#[cfg(feature = "transaction-status")]
+pub mod transaction_status;
 pub mod utils;
 
 pub use harness::*;
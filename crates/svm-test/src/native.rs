@@ -0,0 +1,458 @@
+//! Native execution of `solana-program` processors, see
+//! [`crate::Svm::add_native_program`].
+//!
+//! Mirrors `solana-program-test`'s `processor!`: accounts are serialized with
+//! the SBF ABI & handed to the processor, `invoke`, `msg!`, return data &
+//! sysvar getters are routed back into the runtime via [`SyscallStubs`].
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::{Once, OnceLock, RwLock};
+
+use solana_bpf_loader_program::serialization::serialize_parameters;
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::{declare_process_instruction, stable_log};
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::entrypoint::{deserialize, ProcessInstruction, ProgramResult, SUCCESS};
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::program_error::{ProgramError, UNSUPPORTED_SYSVAR};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::stable_layout::stable_instruction::StableInstruction;
+use solana_sdk::sysvar::Sysvar;
+use solana_timings::ExecuteTimings;
+
+static PROCESSORS: OnceLock<RwLock<HashMap<Pubkey, ProcessInstruction>>> = OnceLock::new();
+
+thread_local! {
+    static INVOKE_CONTEXT: Cell<usize> = const { Cell::new(0) };
+    /// The error of the last failed CPI, see [`cpi_error`].
+    static CPI_ERROR: RefCell<Option<InstructionError>> = const { RefCell::new(None) };
+}
+
+/// Registers `processor` as the implementation of `program_id`.
+///
+/// Registrations are process wide, tests running on other threads with the
+/// same program id will share the processor.
+pub(crate) fn register(program_id: Pubkey, processor: ProcessInstruction) {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        solana_sdk::program_stubs::set_syscall_stubs(Box::new(SyscallStubs));
+    });
+
+    PROCESSORS
+        .get_or_init(Default::default)
+        .write()
+        .unwrap()
+        .insert(program_id, processor);
+}
+
+declare_process_instruction!(NativeProcessor, 1, |invoke_context| {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let program_id = *instruction_context.get_last_program_key(transaction_context)?;
+    let processor = PROCESSORS
+        .get()
+        .and_then(|processors| processors.read().unwrap().get(&program_id).copied())
+        .ok_or(InstructionError::UnsupportedProgramId)?;
+
+    invoke_processor(processor, invoke_context)
+});
+
+fn invoke_processor(
+    processor: ProcessInstruction,
+    invoke_context: &mut InvokeContext,
+) -> Result<(), InstructionError> {
+    let _guard = set_invoke_context(invoke_context);
+
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let instruction_data = instruction_context.get_instruction_data();
+
+    // Serialize with the SBF ABI & deserialize into `AccountInfo`s.
+    let (mut parameter_bytes, ..) =
+        serialize_parameters(transaction_context, instruction_context, true)?;
+    let (program_id, account_infos, _) =
+        unsafe { deserialize(parameter_bytes.as_slice_mut().as_mut_ptr()) };
+
+    let result = processor(program_id, &account_infos, instruction_data);
+    let cpi_error = CPI_ERROR.take();
+    if let Err(err) = result {
+        return Err(cpi_error.unwrap_or_else(|| InstructionError::from(u64::from(err))));
+    }
+
+    // Re-fetch the instruction context, a CPI may have invalidated it.
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let account_infos: HashMap<_, _> = account_infos
+        .into_iter()
+        .map(|account_info| (*account_info.key, account_info))
+        .collect();
+    for index in 0..instruction_context.get_number_of_instruction_accounts() {
+        let mut account =
+            instruction_context.try_borrow_instruction_account(transaction_context, index)?;
+        let Some(account_info) = account_infos
+            .get(account.get_key())
+            .filter(|_| account.is_writable())
+        else {
+            continue;
+        };
+
+        write_back(&mut account, account_info)?;
+    }
+
+    Ok(())
+}
+
+/// Copies the lamports, data & owner of `account_info` into `account`.
+fn write_back(
+    account: &mut solana_sdk::transaction_context::BorrowedAccount,
+    account_info: &AccountInfo,
+) -> Result<(), InstructionError> {
+    if account.get_lamports() != account_info.lamports() {
+        account.set_lamports(account_info.lamports())?;
+    }
+
+    let data = account_info.try_borrow_data().unwrap();
+    match account
+        .can_data_be_resized(data.len())
+        .and_then(|_| account.can_data_be_changed())
+    {
+        Ok(()) => account.set_data_from_slice(&data)?,
+        Err(err) if account.get_data() != *data => return Err(err),
+        Err(_) => {}
+    }
+
+    // The owner is set last so the lamports & data can still be modified.
+    if account.get_owner() != account_info.owner {
+        account.set_owner(account_info.owner.as_ref())?;
+    }
+
+    Ok(())
+}
+
+/// Restores the previous [`INVOKE_CONTEXT`] on drop, so the pointer never
+/// outlives the processor call (even if it panics).
+struct InvokeContextGuard(usize);
+
+impl Drop for InvokeContextGuard {
+    fn drop(&mut self) {
+        INVOKE_CONTEXT.set(self.0);
+    }
+}
+
+/// Records a failed CPI's `err` & returns the closest [`ProgramError`] for the
+/// processor to propagate.
+///
+/// Under SBF a failed `invoke` aborts the caller with `err`, so if the
+/// processor then fails the transaction fails with `err` (i.e.
+/// `PrivilegeEscalation`, which has no [`ProgramError`] equivalent).
+fn cpi_error(err: InstructionError) -> ProgramError {
+    let program_error =
+        ProgramError::try_from(err.clone()).unwrap_or(ProgramError::InvalidArgument);
+    CPI_ERROR.set(Some(err));
+
+    program_error
+}
+
+fn set_invoke_context(invoke_context: &mut InvokeContext) -> InvokeContextGuard {
+    InvokeContextGuard(INVOKE_CONTEXT.replace(invoke_context as *mut InvokeContext as usize))
+}
+
+fn get_invoke_context<'a, 'b>() -> &'a mut InvokeContext<'b> {
+    let ptr = INVOKE_CONTEXT.get();
+    assert_ne!(ptr, 0, "Native processor called outside of a transaction");
+
+    unsafe { &mut *(ptr as *mut InvokeContext) }
+}
+
+fn get_sysvar<T>(sysvar: Result<std::sync::Arc<T>, InstructionError>, var_addr: *mut u8) -> u64
+where
+    T: Sysvar + Clone,
+{
+    let invoke_context = get_invoke_context();
+    invoke_context
+        .consume_checked(invoke_context.get_compute_budget().sysvar_base_cost + T::size_of() as u64)
+        .unwrap_or_else(|err| panic!("Failed to consume sysvar CUs; err={err}"));
+
+    match sysvar {
+        Ok(sysvar) => {
+            unsafe { *(var_addr as *mut T) = T::clone(&sysvar) };
+
+            SUCCESS
+        }
+        Err(_) => UNSUPPORTED_SYSVAR,
+    }
+}
+
+struct SyscallStubs;
+
+impl solana_sdk::program_stubs::SyscallStubs for SyscallStubs {
+    fn sol_log(&self, message: &str) {
+        stable_log::program_log(&get_invoke_context().get_log_collector(), message);
+    }
+
+    fn sol_log_data(&self, data: &[&[u8]]) {
+        stable_log::program_data(&get_invoke_context().get_log_collector(), data);
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let instruction = StableInstruction::from(instruction.clone());
+        let invoke_context = get_invoke_context();
+        let transaction_context = &invoke_context.transaction_context;
+        let instruction_context = transaction_context
+            .get_current_instruction_context()
+            .unwrap();
+        let caller = *instruction_context
+            .get_last_program_key(transaction_context)
+            .unwrap();
+
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (instruction_accounts, program_indices) = invoke_context
+            .prepare_instruction(&instruction, &signers)
+            .map_err(|err| {
+                stable_log::program_failure(
+                    &invoke_context.get_log_collector(),
+                    &instruction.program_id,
+                    &err,
+                );
+
+                cpi_error(err)
+            })?;
+
+        // Copy the caller's modifications into the runtime.
+        let transaction_context = &invoke_context.transaction_context;
+        let instruction_context = transaction_context
+            .get_current_instruction_context()
+            .unwrap();
+        let mut writable = Vec::with_capacity(instruction_accounts.len());
+        for instruction_account in &instruction_accounts {
+            let key = transaction_context
+                .get_key_of_account_at_index(instruction_account.index_in_transaction)
+                .unwrap();
+            let account_info = account_infos
+                .iter()
+                .find(|account_info| account_info.key == key)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let mut account = instruction_context
+                .try_borrow_instruction_account(
+                    transaction_context,
+                    instruction_account.index_in_caller,
+                )
+                .unwrap();
+            write_back(&mut account, account_info).map_err(|err| {
+                stable_log::program_failure(
+                    &invoke_context.get_log_collector(),
+                    &instruction.program_id,
+                    &err,
+                );
+
+                cpi_error(err)
+            })?;
+
+            if instruction_account.is_writable {
+                writable.push((instruction_account.index_in_caller, account_info));
+            }
+        }
+
+        invoke_context
+            .process_instruction(
+                &instruction.data,
+                &instruction_accounts,
+                &program_indices,
+                &mut 0,
+                &mut ExecuteTimings::default(),
+            )
+            .map_err(cpi_error)?;
+
+        // Copy the callee's modifications back into the caller's accounts.
+        let transaction_context = &invoke_context.transaction_context;
+        let instruction_context = transaction_context
+            .get_current_instruction_context()
+            .unwrap();
+        for (index_in_caller, account_info) in writable {
+            let account = instruction_context
+                .try_borrow_instruction_account(transaction_context, index_in_caller)
+                .unwrap();
+            **account_info.try_borrow_mut_lamports()? = account.get_lamports();
+            // NB: `owner` points into the caller's (mutable) parameter buffer.
+            if account_info.owner != account.get_owner() {
+                account_info.assign(account.get_owner());
+            }
+            if account_info.data_len() != account.get_data().len() {
+                account_info.realloc(account.get_data().len(), false)?;
+            }
+            account_info
+                .try_borrow_mut_data()?
+                .copy_from_slice(account.get_data());
+        }
+
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        get_sysvar(get_invoke_context().get_sysvar_cache().get_clock(), var_addr)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        get_sysvar(get_invoke_context().get_sysvar_cache().get_epoch_schedule(), var_addr)
+    }
+
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        get_sysvar(get_invoke_context().get_sysvar_cache().get_epoch_rewards(), var_addr)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        get_sysvar(get_invoke_context().get_sysvar_cache().get_rent(), var_addr)
+    }
+
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        get_sysvar(
+            get_invoke_context()
+                .get_sysvar_cache()
+                .get_last_restart_slot(),
+            var_addr,
+        )
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        let (program_id, data) = get_invoke_context().transaction_context.get_return_data();
+
+        Some((*program_id, data.to_vec()))
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let transaction_context = &mut get_invoke_context().transaction_context;
+        let program_id = *transaction_context
+            .get_current_instruction_context()
+            .and_then(|context| context.get_last_program_key(transaction_context))
+            .unwrap();
+        transaction_context
+            .set_return_data(program_id, data.to_vec())
+            .unwrap();
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        get_invoke_context().get_stack_height() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::account::Account;
+    use solana_sdk::instruction::AccountMeta;
+    use solana_sdk::program::{invoke, invoke_signed, set_return_data};
+    use solana_sdk::transaction::{Transaction, TransactionError};
+    use solana_sdk::{msg, system_instruction, system_program};
+
+    use super::*;
+    use crate::svm::DefaultLoader;
+    use crate::utils::{test_payer_keypair, TEST_PAYER};
+    use crate::Svm;
+
+    fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        let [vault, recipient, _] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        msg!("Withdraw: {}", data[0]);
+        let (_, bump) = Pubkey::find_program_address(&[b"vault"], program_id);
+        invoke_signed(
+            &system_instruction::transfer(vault.key, recipient.key, data[0].into()),
+            &[vault.clone(), recipient.clone()],
+            &[&[b"vault", &[bump]]],
+        )?;
+        set_return_data(&recipient.lamports().to_le_bytes());
+
+        Ok(())
+    }
+
+    fn escalate(_: &Pubkey, accounts: &[AccountInfo], _: &[u8]) -> ProgramResult {
+        let [vault, recipient, _] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        // Missing the vault's signer seeds.
+        invoke(
+            &system_instruction::transfer(vault.key, recipient.key, 100),
+            &[vault.clone(), recipient.clone()],
+        )
+    }
+
+    #[test]
+    fn native_cpi() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        let program_id = Pubkey::new_unique();
+        let vault = Pubkey::find_program_address(&[b"vault"], &program_id).0;
+        let recipient = Pubkey::new_unique();
+        svm.add_native_program(program_id, process_instruction);
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        svm.set(vault, Account { lamports: 10u64.pow(9), ..Default::default() });
+        svm.set(recipient, Account { lamports: 10u64.pow(9), ..Default::default() });
+
+        let ix = Instruction::new_with_bytes(
+            program_id,
+            &[100],
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&TEST_PAYER),
+            &[test_payer_keypair()],
+            svm.blockhash(),
+        );
+        let meta = svm.execute_transaction(tx).unwrap();
+
+        assert!(meta.logs.contains(&"Program log: Withdraw: 100".to_owned()));
+        assert_eq!(meta.return_data.data, (10u64.pow(9) + 100).to_le_bytes());
+        assert_eq!(svm.get(&vault).unwrap().lamports, 10u64.pow(9) - 100);
+    }
+
+    #[test]
+    fn native_cpi_privilege_escalation() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        let program_id = Pubkey::new_unique();
+        let vault = Pubkey::find_program_address(&[b"vault"], &program_id).0;
+        let recipient = Pubkey::new_unique();
+        svm.add_native_program(program_id, escalate);
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        svm.set(vault, Account { lamports: 10u64.pow(9), ..Default::default() });
+
+        let ix = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&TEST_PAYER),
+            &[test_payer_keypair()],
+            svm.blockhash(),
+        );
+        let err = svm.execute_transaction(tx).unwrap_err().err;
+
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::PrivilegeEscalation)
+        );
+        assert_eq!(svm.get(&vault).unwrap().lamports, 10u64.pow(9));
+    }
+}
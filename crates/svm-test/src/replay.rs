@@ -0,0 +1,587 @@
+//! Replays historical transactions against their recorded `getTransaction`
+//! meta, see [`crate::Harness::replay_transaction`].
+use std::collections::{BTreeSet, HashMap};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader_upgradeable::{self, get_program_data_address};
+use solana_sdk::clock::{Clock, Slot, UnixTimestamp};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::reserved_account_keys::ReservedAccountKeys;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiLoadedAddresses, UiTransactionEncoding,
+    UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+
+use crate::differential::{is_compute_units_log, Mismatch};
+use crate::svm::{FailedTransactionMetadata, TransactionMetadata};
+use crate::{AccountLoader, Svm};
+
+const TOKEN_PROGRAMS: &[Pubkey] = &[
+    pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+    pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"),
+];
+
+/// Fetches `signature` via `getTransaction`.
+pub async fn fetch_transaction(
+    rpc: &RpcClient,
+    signature: &Signature,
+) -> EncodedConfirmedTransactionWithStatusMeta {
+    rpc.get_transaction_with_config(
+        signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        },
+    )
+    .await
+    .unwrap_or_else(|err| panic!("Failed to fetch transaction; signature={signature}; err={err}"))
+}
+
+/// A historical transaction, its recorded meta & a loader holding the
+/// accounts it touched.
+#[derive(Debug, Clone)]
+pub struct Replay<L> {
+    pub signature: Signature,
+    pub slot: Slot,
+    pub block_time: Option<UnixTimestamp>,
+    pub transaction: VersionedTransaction,
+    pub meta: UiTransactionStatusMeta,
+    pub loader: L,
+}
+
+impl<L> Replay<L>
+where
+    L: AccountLoader,
+{
+    pub fn new(recorded: EncodedConfirmedTransactionWithStatusMeta, loader: L) -> Self {
+        let transaction = recorded
+            .transaction
+            .transaction
+            .decode()
+            .unwrap_or_else(|| panic!("Failed to decode transaction; slot={}", recorded.slot));
+        let signature = transaction.signatures[0];
+        let meta = recorded
+            .transaction
+            .meta
+            .unwrap_or_else(|| panic!("Missing transaction meta; signature={signature}"));
+
+        Replay {
+            signature,
+            slot: recorded.slot,
+            block_time: recorded.block_time,
+            transaction,
+            meta,
+            loader,
+        }
+    }
+
+    /// Static keys followed by the addresses loaded from lookup tables, the
+    /// order of the recorded balances.
+    pub fn account_keys(&self) -> Vec<Pubkey> {
+        let loaded = Option::<UiLoadedAddresses>::from(self.meta.loaded_addresses.clone())
+            .unwrap_or_default();
+        let parse = |key: &String| {
+            key.parse().unwrap_or_else(|err| {
+                panic!("Failed to parse loaded address; key={key}; err={err}")
+            })
+        };
+
+        self.transaction
+            .message
+            .static_account_keys()
+            .iter()
+            .copied()
+            .chain(loaded.writable.iter().chain(&loaded.readonly).map(parse))
+            .collect()
+    }
+
+    /// Loads every account the transaction touched, including lookup tables,
+    /// program data & mints, i.e. to record them.
+    pub fn preload(&self) {
+        let tables = self
+            .transaction
+            .message
+            .address_table_lookups()
+            .into_iter()
+            .flatten()
+            .map(|lookup| lookup.account_key);
+
+        for key in self
+            .account_keys()
+            .into_iter()
+            .chain(tables)
+            .chain(self.mints())
+        {
+            let account = self.loader.load(&key);
+            if account.executable && account.owner == bpf_loader_upgradeable::ID {
+                self.loader.load(&get_program_data_address(&key));
+            }
+        }
+    }
+
+    /// The touched accounts as they were before the transaction, as far as the
+    /// recording allows.
+    ///
+    /// Accounts are loaded in their current state & have their lamports &
+    /// token amounts rewound to the recorded pre balances. Other data can not
+    /// be recovered, programs & reserved accounts are left to the loader.
+    pub fn pre_state(&self) -> Vec<(Pubkey, Account)> {
+        let keys = self.account_keys();
+        let reserved = ReservedAccountKeys::new_all_activated();
+        let amounts = token_amounts(
+            &keys,
+            Option::<Vec<UiTransactionTokenBalance>>::from(self.meta.pre_token_balances.clone())
+                .unwrap_or_default(),
+        );
+
+        keys.iter()
+            .zip(&self.meta.pre_balances)
+            .filter(|(key, _)| !reserved.is_reserved(key))
+            .filter_map(|(key, lamports)| {
+                let mut account = self.loader.load(key);
+                match (account.executable, lamports) {
+                    (true, _) => return None,
+                    (false, 0) => return Some((*key, Account::default())),
+                    (false, lamports) => account.lamports = *lamports,
+                }
+                if let Some(amount) = amounts
+                    .get(key)
+                    .filter(|_| TOKEN_PROGRAMS.contains(&account.owner) && account.data.len() >= 72)
+                {
+                    account.data[64..72].copy_from_slice(&amount.to_le_bytes());
+                }
+
+                Some((*key, account))
+            })
+            .collect()
+    }
+
+    /// The mints of the recorded token balances.
+    pub fn mints(&self) -> BTreeSet<Pubkey> {
+        [&self.meta.pre_token_balances, &self.meta.post_token_balances]
+            .into_iter()
+            .flat_map(|balances| {
+                Option::<Vec<UiTransactionTokenBalance>>::from(balances.clone()).unwrap_or_default()
+            })
+            .map(|balance| {
+                let mint = balance.mint;
+                mint.parse()
+                    .unwrap_or_else(|err| panic!("Failed to parse mint; mint={mint}; err={err}"))
+            })
+            .collect()
+    }
+
+    /// An [`Svm`] holding [`Self::pre_state`] & [`Self::mints`] at the
+    /// recorded slot, with blockhash checks disabled.
+    pub fn svm(&self) -> Svm<L>
+    where
+        L: Clone,
+    {
+        let mut svm = Svm::new(self.loader.clone());
+        svm.set_blockhash_check(false);
+
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.slot = self.slot;
+        clock.unix_timestamp = self.block_time.unwrap_or(clock.unix_timestamp);
+        svm.set_sysvar(&clock);
+
+        for (key, account) in self.pre_state() {
+            svm.set(key, account);
+        }
+        // NB: Token balances are only reported if the mint is loaded.
+        for mint in self.mints() {
+            if svm.get(&mint).is_none() {
+                svm.set(mint, self.loader.load(&mint));
+            }
+        }
+
+        svm
+    }
+
+    /// Executes the transaction in `svm`, returning the result & how it
+    /// diverged from the recording (recorded on the left, replayed on the
+    /// right).
+    ///
+    /// Token balances are only compared with the `spl` feature.
+    #[allow(clippy::result_large_err)]
+    pub fn execute<M>(
+        &self,
+        svm: &mut Svm<M>,
+    ) -> (Result<TransactionMetadata, FailedTransactionMetadata>, Vec<Mismatch>)
+    where
+        M: AccountLoader,
+    {
+        let result = svm.execute_transaction(self.transaction.clone());
+        let Some(entry) = svm.ledger().get(&self.signature) else {
+            // Rejected before execution, i.e. the fee payer cannot pay the fee.
+            let right = result.as_ref().err().map(|failed| failed.err.clone());
+
+            return (result, vec![Mismatch::Result { left: self.meta.err.clone(), right }]);
+        };
+
+        let mut mismatches = Vec::default();
+        if self.meta.err != entry.err {
+            mismatches
+                .push(Mismatch::Result { left: self.meta.err.clone(), right: entry.err.clone() });
+        }
+        if let Some(left) = Option::<u64>::from(self.meta.compute_units_consumed.clone())
+            .filter(|left| *left != entry.meta.compute_units_consumed)
+        {
+            mismatches
+                .push(Mismatch::ComputeUnits { left, right: entry.meta.compute_units_consumed });
+        }
+        let strip = |logs: &[String]| -> Vec<String> {
+            logs.iter()
+                .filter(|log| !is_compute_units_log(log))
+                .cloned()
+                .collect()
+        };
+        let left =
+            strip(&Option::<Vec<String>>::from(self.meta.log_messages.clone()).unwrap_or_default());
+        let right = strip(&entry.meta.logs);
+        if left != right {
+            mismatches.push(Mismatch::Logs { left, right });
+        }
+        let keys = self.account_keys();
+        for ((key, left), right) in keys
+            .iter()
+            .zip(&self.meta.post_balances)
+            .zip(&entry.post_balances)
+        {
+            if left != right {
+                mismatches.push(Mismatch::Balance { key: *key, left: *left, right: *right });
+            }
+        }
+        #[cfg(feature = "spl")]
+        {
+            let left = token_amounts(
+                &keys,
+                Option::<Vec<UiTransactionTokenBalance>>::from(
+                    self.meta.post_token_balances.clone(),
+                )
+                .unwrap_or_default(),
+            );
+            let right: HashMap<_, _> = entry
+                .post_token_balances
+                .iter()
+                .map(|balance| (balance.account, balance.amount))
+                .collect();
+            let accounts: BTreeSet<_> = left.keys().chain(right.keys()).copied().collect();
+            for key in accounts {
+                let (left, right) = (left.get(&key).copied(), right.get(&key).copied());
+                if left != right {
+                    mismatches.push(Mismatch::TokenBalance { key, left, right });
+                }
+            }
+        }
+
+        (result, mismatches)
+    }
+}
+
+/// The recorded token amounts by account, `keys` as per
+/// [`Replay::account_keys`].
+fn token_amounts(
+    keys: &[Pubkey],
+    balances: Vec<UiTransactionTokenBalance>,
+) -> HashMap<Pubkey, u64> {
+    balances
+        .into_iter()
+        .map(|balance| {
+            let amount = &balance.ui_token_amount.amount;
+            let amount = amount.parse().unwrap_or_else(|err| {
+                panic!("Failed to parse token amount; amount={amount}; err={err}")
+            });
+
+            (keys[balance.account_index as usize], amount)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+    use solana_account_decoder::{UiAccount, UiAccountEncoding};
+    use solana_sdk::address_lookup_table::state::{AddressLookupTable, LookupTableMeta};
+    use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+    use solana_sdk::message::{v0, VersionedMessage};
+    #[cfg(feature = "spl")]
+    use solana_sdk::signature::{Keypair, Signer};
+    #[cfg(feature = "spl")]
+    use solana_sdk::transaction::Transaction;
+    use solana_sdk::{address_lookup_table, system_instruction, system_program};
+
+    use super::*;
+    #[cfg(feature = "spl")]
+    use crate::spl::SplProgram;
+    use crate::svm::DefaultLoader;
+    #[cfg(feature = "spl")]
+    use crate::utils::spl_token::{mock_ata, mock_mint, MintBuilder};
+    use crate::utils::{test_payer_keypair, TEST_PAYER};
+    use crate::Harness;
+
+    /// Loads accounts from the stand-in server.
+    struct StandIn(solana_client::rpc_client::RpcClient);
+
+    impl AccountLoader for StandIn {
+        fn load(&self, key: &Pubkey) -> Account {
+            self.0
+                .get_account_with_commitment(key, CommitmentConfig::confirmed())
+                .unwrap()
+                .value
+                .unwrap_or_default()
+        }
+    }
+
+    /// Serves `getTransaction` & `getAccountInfo` over HTTP, returning the url.
+    fn stand_in(transaction: Value, accounts: HashMap<Pubkey, Account>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let (transaction, accounts) = (transaction.clone(), accounts.clone());
+                std::thread::spawn(move || serve(stream.unwrap(), &transaction, &accounts));
+            }
+        });
+
+        url
+    }
+
+    fn serve(mut stream: TcpStream, transaction: &Value, accounts: &HashMap<Pubkey, Account>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        loop {
+            // Read headers until the blank line, keeping the content length.
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    return;
+                }
+                match line.trim_end().split_once(':') {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        length = value.trim().parse().unwrap();
+                    }
+                    None if line.trim_end().is_empty() => break,
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let request: Value = serde_json::from_slice(&body).unwrap();
+            let result = match request["method"].as_str().unwrap() {
+                "getTransaction" => transaction.clone(),
+                "getAccountInfo" => {
+                    let key = request["params"][0].as_str().unwrap().parse().unwrap();
+                    let account = accounts.get(&key).map(|account| {
+                        UiAccount::encode(&key, account, UiAccountEncoding::Base64, None, None)
+                    });
+
+                    json!({ "context": { "slot": 10 }, "value": account })
+                }
+                method => panic!("Unexpected method; method={method}"),
+            };
+            let response =
+                json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        }
+    }
+
+    /// A funded payer at slot 10.
+    fn mainnet() -> Svm<DefaultLoader> {
+        let mut mainnet: Svm<DefaultLoader> = Svm::default();
+        mainnet.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        let mut clock = mainnet.get_sysvar::<Clock>();
+        clock.slot = 10;
+        mainnet.set_sysvar(&clock);
+
+        mainnet
+    }
+
+    /// Executes `tx` on `mainnet`, returning its signature & the url of a
+    /// stand-in serving it along with the post-state of `accounts`.
+    fn record(
+        mainnet: &mut Svm<DefaultLoader>,
+        tx: impl Into<VersionedTransaction>,
+        accounts: &[Pubkey],
+    ) -> (Signature, String) {
+        let signature = mainnet.execute_transaction(tx).unwrap().signature;
+        let transaction = mainnet
+            .get_transaction(&signature, UiTransactionEncoding::Base64)
+            .unwrap();
+        let accounts = accounts
+            .iter()
+            .map(|key| (*key, mainnet.get(key).unwrap()))
+            .collect();
+
+        (signature, stand_in(serde_json::to_value(transaction).unwrap(), accounts))
+    }
+
+    fn replay(signature: &Signature, url: String) -> Replay<Arc<StandIn>> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let recorded = runtime.block_on(fetch_transaction(&RpcClient::new(url.clone()), signature));
+
+        Replay::new(recorded, Arc::new(StandIn(solana_client::rpc_client::RpcClient::new(url))))
+    }
+
+    /// Records a transfer to an address in a lookup table, returning the
+    /// signature, recipient & stand-in url.
+    fn lookup_table_transfer_on_mainnet() -> (Signature, Pubkey, String) {
+        let mut mainnet = mainnet();
+        let bob = Pubkey::new_unique();
+        let table = Pubkey::new_unique();
+        let state = AddressLookupTable {
+            meta: LookupTableMeta::default(),
+            addresses: Cow::Owned(vec![bob]),
+        };
+        mainnet.set(
+            table,
+            Account {
+                lamports: 10u64.pow(9),
+                data: state.serialize_for_tests().unwrap(),
+                owner: address_lookup_table::program::ID,
+                ..Default::default()
+            },
+        );
+
+        let message = v0::Message::try_compile(
+            &TEST_PAYER,
+            &[system_instruction::transfer(&TEST_PAYER, &bob, 10u64.pow(6))],
+            &[AddressLookupTableAccount { key: table, addresses: vec![bob] }],
+            mainnet.blockhash(),
+        )
+        .unwrap();
+        let tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&test_payer_keypair()])
+                .unwrap();
+        let (signature, url) = record(&mut mainnet, tx, &[TEST_PAYER, bob, table]);
+
+        (signature, bob, url)
+    }
+
+    #[test]
+    fn lookup_table_transfer() {
+        // Record from the stand-in, which only knows the post-state.
+        let (signature, bob, url) = lookup_table_transfer_on_mainnet();
+        let replay = replay(&signature, url);
+
+        assert_eq!(replay.account_keys(), [TEST_PAYER, system_program::ID, bob]);
+        assert_eq!(
+            replay.pre_state(),
+            [
+                (TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() }),
+                (bob, Account::default()),
+            ]
+        );
+
+        // Replaying from the rebuilt pre-state matches the recording.
+        let mut svm = replay.svm();
+        let (result, mismatches) = replay.execute(&mut svm);
+        assert!(result.is_ok());
+        svm.assert_no_mismatches(&mismatches);
+
+        // Diverging state is reported.
+        let mut svm = replay.svm();
+        svm.set(TEST_PAYER, Account { lamports: 5000, ..Default::default() });
+        let (_, mismatches) = replay.execute(&mut svm);
+        assert!(matches!(mismatches[0], Mismatch::Result { left: None, right: Some(_) }));
+    }
+
+    #[cfg(feature = "spl")]
+    #[test]
+    fn token_transfer() {
+        let mut mainnet = mainnet();
+        mainnet.load_spl_program(SplProgram::Token);
+        let alice = Keypair::new();
+        let mint = mock_mint(&mut mainnet, Pubkey::new_unique(), &MintBuilder::new(6));
+        let from = mock_ata(&mut mainnet, mint, alice.pubkey(), 5 * 10u64.pow(6));
+        let to = mock_ata(&mut mainnet, mint, TEST_PAYER, 0);
+        let ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            &from,
+            &to,
+            &alice.pubkey(),
+            &[],
+            10u64.pow(6),
+        )
+        .unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&TEST_PAYER),
+            &[test_payer_keypair(), &alice],
+            mainnet.blockhash(),
+        );
+        let (signature, url) =
+            record(&mut mainnet, tx, &[TEST_PAYER, from, to, mint, spl_token::ID]);
+        let replay = replay(&signature, url);
+
+        // The mint is not part of the transaction but is needed for balances.
+        assert_eq!(replay.mints(), BTreeSet::from([mint]));
+        let mut svm = replay.svm();
+        let (result, mismatches) = replay.execute(&mut svm);
+        assert!(result.is_ok());
+        svm.assert_no_mismatches(&mismatches);
+
+        // Diverging token amounts are reported.
+        let mut svm = replay.svm();
+        mock_ata(&mut svm, mint, alice.pubkey(), 3 * 10u64.pow(6));
+        let (_, mismatches) = replay.execute(&mut svm);
+        assert!(mismatches.contains(&Mismatch::TokenBalance {
+            key: from,
+            left: Some(4 * 10u64.pow(6)),
+            right: Some(2 * 10u64.pow(6)),
+        }));
+    }
+
+    /// A temporary test data directory, removed on drop.
+    struct TestData(std::path::PathBuf);
+
+    impl Drop for TestData {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn harness_replay_transaction() {
+        let (signature, _, url) = lookup_table_transfer_on_mainnet();
+        let test_data = TestData(std::env::temp_dir().join(format!("svm-test-replay-{signature}")));
+
+        // Recording fetches the transaction & its accounts from the RPC.
+        let replay = Harness::get().replay_transaction_with(&signature, Some(&url), &test_data.0);
+        let mut svm = replay.svm();
+        svm.assert_no_mismatches(&replay.execute(&mut svm).1);
+        drop((svm, replay));
+        assert!(test_data
+            .0
+            .join(format!("replay/{signature}.tx.json"))
+            .exists());
+        assert!(test_data
+            .0
+            .join(format!("replay/{signature}.json.gz"))
+            .exists());
+
+        // Offline replays only use the recording.
+        let replay = Harness::get().replay_transaction_with(&signature, None, &test_data.0);
+        let mut svm = replay.svm();
+        svm.assert_no_mismatches(&replay.execute(&mut svm).1);
+    }
+}
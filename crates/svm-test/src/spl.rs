@@ -1,5 +1,150 @@
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+// Builds shipped with `solana-program-test` 1.18 (`src/programs`), their
+// hashes are pinned by `tests::bundled_elf_hashes`.
+const TOKEN_3_5_0: &[u8] = include_bytes!("../elf/spl_token-3.5.0.so");
+const TOKEN_2022_1_0_0: &[u8] = include_bytes!("../elf/spl_token_2022-1.0.0.so");
+const ASSOCIATED_TOKEN_ACCOUNT_1_1_1: &[u8] =
+    include_bytes!("../elf/spl_associated_token_account-1.1.1.so");
+const MEMO_1_0_0: &[u8] = include_bytes!("../elf/spl_memo-1.0.0.so");
+const MEMO_3_0_0: &[u8] = include_bytes!("../elf/spl_memo-3.0.0.so");
+
+/// Programs that can be loaded via [`crate::Svm::load_spl_program`].
+///
+/// All programs are bundled. Address Lookup Table is a builtin & is always
+/// available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SplProgram {
     Token,
     Token2022,
     AssociatedTokenAccount,
+    Memo,
+    MemoV1,
+}
+
+impl SplProgram {
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            SplProgram::Token => spl_token::ID,
+            SplProgram::Token2022 => spl_token_2022::ID,
+            SplProgram::AssociatedTokenAccount => spl_associated_token_account::ID,
+            SplProgram::Memo => pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+            SplProgram::MemoV1 => pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo"),
+        }
+    }
+
+    /// The ELF file name (without `.so`) used when a version that is not
+    /// bundled is requested.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SplProgram::Token => "spl_token",
+            SplProgram::Token2022 => "spl_token_2022",
+            SplProgram::AssociatedTokenAccount => "spl_associated_token_account",
+            SplProgram::Memo => "spl_memo",
+            SplProgram::MemoV1 => "spl_memo_v1",
+        }
+    }
+
+    /// The bundled ELF. Token, Token-2022 & Associated Token Account are the
+    /// builds shipped with litesvm.
+    pub fn bundled_elf(&self) -> &'static [u8] {
+        match self {
+            SplProgram::Token => litesvm::spl::TOKEN_ELF,
+            SplProgram::Token2022 => litesvm::spl::TOKEN_2022_ELF,
+            SplProgram::AssociatedTokenAccount => litesvm::spl::ASSOCIATED_TOKEN_ACCOUNT_ELF,
+            SplProgram::Memo => MEMO_3_0_0,
+            SplProgram::MemoV1 => MEMO_1_0_0,
+        }
+    }
+
+    /// The bundled ELF of a pinned `version`, if any.
+    pub fn bundled_version(&self, version: &str) -> Option<&'static [u8]> {
+        match (self, version) {
+            (SplProgram::Token, "3.5.0") => Some(TOKEN_3_5_0),
+            (SplProgram::Token2022, "1.0.0") => Some(TOKEN_2022_1_0_0),
+            (SplProgram::AssociatedTokenAccount, "1.1.1") => Some(ASSOCIATED_TOKEN_ACCOUNT_1_1_1),
+            (SplProgram::Memo, "3.0.0") => Some(MEMO_3_0_0),
+            (SplProgram::MemoV1, "1.0.0") => Some(MEMO_1_0_0),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::account::Account;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::transaction::Transaction;
+
+    use super::*;
+    use crate::account_data::Packed;
+    use crate::svm::DefaultLoader;
+    use crate::utils::spl_token::{mock_ata, mock_mint, MintBuilder};
+    use crate::utils::{elf_hash, test_payer_keypair, TEST_PAYER};
+    use crate::Svm;
+
+    fn svm() -> Svm<DefaultLoader> {
+        let mut svm = Svm::default();
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+
+        svm
+    }
+
+    fn execute(svm: &mut Svm<DefaultLoader>, ix: Instruction) -> Vec<String> {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&TEST_PAYER),
+            &[test_payer_keypair()],
+            svm.blockhash(),
+        );
+
+        svm.execute_transaction(tx).unwrap().logs
+    }
+
+    #[test]
+    fn bundled_elf_hashes() {
+        for (elf, hash) in [
+            (TOKEN_3_5_0, "10c2625f35594bc23489eb59f5d0df7a0382926d3cbd47e237a7c6917a8b6143"),
+            (TOKEN_2022_1_0_0, "d941635f5df9dcb42e2fd7f25149051048dd27d02c7cb8dbb7a9e39933722fd3"),
+            (
+                ASSOCIATED_TOKEN_ACCOUNT_1_1_1,
+                "15caf33e03d4aaad4b31330406ccd972840be98e9776c1b89e03d2719a4722c6",
+            ),
+            (MEMO_1_0_0, "fc6c9b6c1124feed25cb44a441790c56146c684a3e5e3853c421289b1972d2bf"),
+            (MEMO_3_0_0, "68d9bbae7023f8f51d32d0f6ff8a7003921c97c99e77921ccd0f51adcf6295db"),
+        ] {
+            assert_eq!(elf_hash(elf), hash);
+        }
+    }
+
+    #[test]
+    fn bundled_memo() {
+        let mut svm = svm();
+        for program in [SplProgram::Memo, SplProgram::MemoV1] {
+            svm.load_spl_program(program);
+            let logs = execute(
+                &mut svm,
+                Instruction::new_with_bytes(program.program_id(), b"hello", vec![]),
+            );
+
+            assert!(logs.last().unwrap().ends_with("success"), "{logs:#?}");
+        }
+    }
+
+    #[test]
+    fn pinned_token_version() {
+        let mut svm = svm();
+        svm.load_spl_program_version(SplProgram::Token, "3.5.0");
+        let mint = mock_mint(&mut svm, Pubkey::new_unique(), &MintBuilder::new(6));
+        let from = mock_ata(&mut svm, mint, TEST_PAYER, 100);
+        let to = mock_ata(&mut svm, mint, Pubkey::new_unique(), 0);
+
+        execute(
+            &mut svm,
+            spl_token::instruction::transfer(&spl_token::ID, &from, &to, &TEST_PAYER, &[], 40)
+                .unwrap(),
+        );
+        assert_eq!(svm.get_as::<Packed<spl_token::state::Account>>(&to).amount, 40);
+    }
 }
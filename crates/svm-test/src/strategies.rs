@@ -0,0 +1,264 @@
+//! [`proptest`] strategies for accounts & SPL state.
+//!
+//! All generated accounts can be written directly via [`crate::Svm::set`].
+use std::sync::OnceLock;
+
+use proptest::option;
+use proptest::prelude::*;
+use solana_sdk::account::Account;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_program;
+
+use crate::ser::JsonAccount;
+use crate::utils::TEST_PAYER;
+
+const POOL_LABELS: &[&str] =
+    &["alice", "bob", "carol", "dave", "admin", "authority", "oracle", "treasury"];
+
+static POOL: OnceLock<Vec<(&'static str, Pubkey)>> = OnceLock::new();
+
+/// The fixed set of pubkeys used by [`pubkey`], along with their labels.
+///
+/// Keys are derived via [`crate::utils::keypair`] so they can also sign.
+pub fn pubkey_pool() -> &'static [(&'static str, Pubkey)] {
+    POOL.get_or_init(|| {
+        std::iter::once(("test_payer", TEST_PAYER))
+            .chain(
+                POOL_LABELS
+                    .iter()
+                    .map(|label| (*label, crate::utils::keypair(label).pubkey())),
+            )
+            .collect()
+    })
+}
+
+pub fn labelled_pubkey() -> impl Strategy<Value = (&'static str, Pubkey)> {
+    proptest::sample::select(pubkey_pool())
+}
+
+pub fn pubkey() -> impl Strategy<Value = Pubkey> {
+    labelled_pubkey().prop_map(|(_, key)| key)
+}
+
+pub fn account() -> impl Strategy<Value = Account> {
+    (any::<u64>(), proptest::collection::vec(any::<u8>(), 0..=1024), pubkey(), any::<u64>())
+        .prop_map(|(lamports, data, owner, rent_epoch)| Account {
+            lamports,
+            data,
+            owner,
+            executable: false,
+            rent_epoch,
+        })
+}
+
+pub fn json_account() -> impl Strategy<Value = JsonAccount> {
+    account().prop_map(JsonAccount::from)
+}
+
+/// A rent-exempt, data-less, system owned account.
+pub fn system_account() -> impl Strategy<Value = Account> {
+    let rent = Rent::default().minimum_balance(0);
+
+    (rent..=1_000_000 * 10u64.pow(9)).prop_map(|lamports| Account {
+        lamports,
+        owner: system_program::ID,
+        ..Default::default()
+    })
+}
+
+/// Compute budget instructions setting (or not) the unit limit, unit price &
+/// heap size.
+pub fn compute_budget() -> impl Strategy<Value = Vec<Instruction>> {
+    (
+        option::of(1u32..=1_400_000),
+        option::of(0u64..=10u64.pow(6)),
+        option::of((1u32..=8).prop_map(|pages| pages * 32 * 1024)),
+    )
+        .prop_map(|(limit, price, heap)| {
+            [
+                limit.map(ComputeBudgetInstruction::set_compute_unit_limit),
+                price.map(ComputeBudgetInstruction::set_compute_unit_price),
+                heap.map(ComputeBudgetInstruction::request_heap_frame),
+            ]
+            .into_iter()
+            .flatten()
+            .collect()
+        })
+}
+
+#[cfg(feature = "spl")]
+pub use spl::*;
+
+#[cfg(feature = "spl")]
+mod spl {
+    use solana_sdk::program_option::COption;
+    use solana_sdk::program_pack::Pack;
+    use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+    use super::*;
+    use crate::utils::pack_to_vec;
+    use crate::utils::spl_token::WSOL;
+
+    /// Offset of `state` in a packed token account.
+    const STATE_OFFSET: usize = 108;
+
+    pub fn token_program() -> impl Strategy<Value = Pubkey> {
+        prop_oneof![Just(spl_token::ID), Just(spl_token_2022::ID)]
+    }
+
+    /// The native (wrapped SOL) mint of `program`, which differs between the
+    /// two token programs.
+    pub fn native_mint(program: Pubkey) -> Pubkey {
+        match program == spl_token_2022::ID {
+            true => spl_token_2022::native_mint::ID,
+            false => WSOL,
+        }
+    }
+
+    /// A valid token account of the legacy token program, see
+    /// [`token_account_state_for`].
+    pub fn token_account_state() -> impl Strategy<Value = TokenAccount> {
+        token_account_state_for(spl_token::ID)
+    }
+
+    /// A valid token account of `program`, native accounts use the program's
+    /// [`native_mint`] & have a matching `is_native`.
+    pub fn token_account_state_for(program: Pubkey) -> impl Strategy<Value = TokenAccount> {
+        let native = native_mint(program);
+
+        (
+            prop_oneof![3 => pubkey(), 1 => Just(native)],
+            pubkey(),
+            0..=u64::MAX / 2,
+            option::of((pubkey(), any::<u64>())),
+            any::<bool>(),
+            option::of(pubkey()),
+        )
+            .prop_map(move |(mint, owner, amount, delegate, frozen, close_authority)| {
+                let mut account = crate::utils::spl_token::token(mint, owner, amount);
+                account.is_native = match mint == native {
+                    true => COption::Some(Rent::default().minimum_balance(TokenAccount::LEN)),
+                    false => COption::None,
+                };
+                if let Some((delegate, delegated_amount)) = delegate {
+                    account.delegate = Some(delegate).into();
+                    account.delegated_amount = delegated_amount.min(amount);
+                }
+                if frozen {
+                    account.state = AccountState::Frozen;
+                }
+                account.close_authority = close_authority.into();
+
+                account
+            })
+    }
+
+    /// A valid token account owned by either token program.
+    pub fn token_account() -> impl Strategy<Value = Account> {
+        token_program().prop_flat_map(|owner| {
+            token_account_state_for(owner).prop_map(move |state| Account {
+                lamports: match state.is_native {
+                    COption::Some(reserve) => reserve + state.amount,
+                    COption::None => Rent::default().minimum_balance(TokenAccount::LEN),
+                },
+                data: pack_to_vec(state),
+                owner,
+                ..Default::default()
+            })
+        })
+    }
+
+    /// A token account that fails to unpack (wrong length, uninitialized or
+    /// corrupt state).
+    pub fn invalid_token_account() -> impl Strategy<Value = Account> {
+        let wrong_length = proptest::collection::vec(any::<u8>(), 0..TokenAccount::LEN);
+        let uninitialized = token_account_state().prop_map(|state| {
+            pack_to_vec(TokenAccount { state: AccountState::Uninitialized, ..state })
+        });
+        let corrupt_state = (token_account_state(), 3u8..=u8::MAX).prop_map(|(state, byte)| {
+            let mut data = pack_to_vec(state);
+            data[STATE_OFFSET] = byte;
+
+            data
+        });
+
+        (prop_oneof![wrong_length, uninitialized, corrupt_state], token_program()).prop_map(
+            |(data, owner)| Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn mint_state() -> impl Strategy<Value = Mint> {
+        (option::of(pubkey()), any::<u64>(), 0..=18u8, option::of(pubkey())).prop_map(
+            |(mint_authority, supply, decimals, freeze_authority)| Mint {
+                mint_authority: mint_authority.into(),
+                supply,
+                decimals,
+                is_initialized: true,
+                freeze_authority: freeze_authority.into(),
+            },
+        )
+    }
+
+    /// A valid mint owned by either token program.
+    pub fn mint_account() -> impl Strategy<Value = Account> {
+        (mint_state(), token_program()).prop_map(|(mint, owner)| Account {
+            lamports: Rent::default().minimum_balance(Mint::LEN),
+            data: pack_to_vec(mint),
+            owner,
+            ..Default::default()
+        })
+    }
+
+    /// A mint that fails to unpack (wrong length or uninitialized).
+    pub fn invalid_mint_account() -> impl Strategy<Value = Account> {
+        let wrong_length = proptest::collection::vec(any::<u8>(), 0..Mint::LEN);
+        let uninitialized =
+            mint_state().prop_map(|mint| pack_to_vec(Mint { is_initialized: false, ..mint }));
+
+        (prop_oneof![wrong_length, uninitialized], token_program()).prop_map(|(data, owner)| {
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner,
+                ..Default::default()
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn system_account_is_rent_exempt(account in system_account()) {
+            prop_assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
+        }
+    }
+
+    #[cfg(feature = "spl")]
+    proptest! {
+        #[test]
+        fn token_accounts_unpack(valid in token_account(), invalid in invalid_token_account()) {
+            use solana_sdk::program_pack::Pack;
+
+            let state = spl_token::state::Account::unpack(&valid.data);
+            prop_assert!(state.is_ok());
+            prop_assert!(spl_token::state::Account::unpack(&invalid.data).is_err());
+
+            // Native accounts use the native mint of their program.
+            let state = state.unwrap();
+            prop_assert_eq!(state.is_native(), state.mint == native_mint(valid.owner));
+        }
+    }
+}
@@ -1,20 +1,40 @@
 use std::collections::{HashMap, HashSet};
 use std::hash::{BuildHasherDefault, DefaultHasher};
+use std::sync::Arc;
 
 use litesvm::types::SimulatedTransactionInfo;
 pub use litesvm::types::{FailedTransactionMetadata, TransactionMetadata, TransactionResult};
 use solana_sdk::account::Account;
+use solana_sdk::bpf_loader_upgradeable::UpgradeableLoaderState;
+use solana_sdk::clock::{Clock, Epoch, Slot};
+#[cfg(feature = "native")]
+use solana_sdk::entrypoint::ProcessInstruction;
+use solana_sdk::epoch_schedule::EpochSchedule;
+use solana_sdk::message::v0::LoadedAddresses;
+use solana_sdk::message::SanitizedMessage;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
 use solana_sdk::reserved_account_keys::ReservedAccountKeys;
+#[cfg(feature = "transaction-status")]
+use solana_sdk::signature::Signature;
+use solana_sdk::signature::{Keypair, Signer};
 use solana_sdk::sysvar::{Sysvar, SysvarId};
-use solana_sdk::transaction::{SanitizedTransaction, VersionedTransaction};
+use solana_sdk::transaction::{SanitizedTransaction, Transaction, VersionedTransaction};
 use solana_sdk::{
     bpf_loader, bpf_loader_upgradeable, compute_budget, ed25519_program, native_loader,
     secp256k1_program, sysvar,
 };
+#[cfg(feature = "transaction-status")]
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
 
+use crate::account_data::AccountData;
+use crate::address_book::AddressBook;
+use crate::differential::{Mismatch, Outcome};
+use crate::ledger::{Ledger, LedgerEntry};
 #[cfg(feature = "spl")]
 use crate::spl::SplProgram;
+#[cfg(feature = "spl")]
+use crate::token_balances::{TokenBalance, TokenBalances};
 use crate::AccountLoader;
 
 pub type DefaultLoader = HashMap<Pubkey, Account, BuildHasherDefault<DefaultHasher>>;
@@ -22,9 +42,20 @@ pub type DefaultLoader = HashMap<Pubkey, Account, BuildHasherDefault<DefaultHash
 const PRE_LOADED: &[Pubkey] =
     &[ed25519_program::ID, secp256k1_program::ID, sysvar::instructions::ID];
 
+/// An immutable snapshot of an [`Svm`]'s accounts, sysvars, blockhash,
+/// programs & [`Ledger`].
+///
+/// Cloning a checkpoint is cheap, restoring one copies the snapshot into the
+/// target [`Svm`].
+#[derive(Clone)]
+pub struct Checkpoint(Arc<litesvm::LiteSVM>, Arc<Ledger>);
+
 pub struct Svm<L = DefaultLoader> {
     inner: litesvm::LiteSVM,
     pub loader: L,
+    /// Labels substituted into rendered output, see [`Svm::render`].
+    pub address_book: AddressBook,
+    ledger: Ledger,
     reserved_account_keys: ReservedAccountKeys,
 }
 
@@ -33,11 +64,7 @@ where
     L: AccountLoader + Default,
 {
     fn default() -> Self {
-        Svm {
-            inner: Self::inner(),
-            loader: Default::default(),
-            reserved_account_keys: ReservedAccountKeys::new_all_activated(),
-        }
+        Svm::new(L::default())
     }
 }
 
@@ -58,14 +85,80 @@ where
             .with_blockhash_check(true)
     }
 
+    /// Creates a new [`Svm`], labelling keys with the loader's
+    /// [`AccountLoader::labels`].
     pub fn new(loader: L) -> Self {
+        let mut address_book = AddressBook::default();
+        address_book.extend(loader.labels());
+
         Svm {
             inner: Self::inner(),
             loader,
+            address_book,
+            ledger: Ledger::default(),
             reserved_account_keys: ReservedAccountKeys::new_all_activated(),
         }
     }
 
+    /// Creates a new [`Svm`] with the state (& [`Ledger`]) of `checkpoint`.
+    pub fn from_checkpoint(loader: L, checkpoint: &Checkpoint) -> Self {
+        Svm {
+            inner: checkpoint.0.as_ref().clone(),
+            ledger: checkpoint.1.as_ref().clone(),
+            ..Svm::new(loader)
+        }
+    }
+
+    /// Toggles blockhash checks, i.e. to replay historical transactions.
+    pub fn set_blockhash_check(&mut self, check: bool) {
+        self.inner = std::mem::take(&mut self.inner).with_blockhash_check(check);
+    }
+
+    /* /////////////////////////////////////////////////////////////////////////////
+                                        Checkpoints
+    ///////////////////////////////////////////////////////////////////////////// */
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(Arc::new(self.inner.clone()), Arc::new(self.ledger.clone()))
+    }
+
+    /// Rewinds all state (including the [`Ledger`]) to `checkpoint`.
+    ///
+    /// Accounts first touched after the checkpoint was taken will be fetched
+    /// from the loader again.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) {
+        self.inner = checkpoint.0.as_ref().clone();
+        self.ledger = checkpoint.1.as_ref().clone();
+    }
+
+    /* /////////////////////////////////////////////////////////////////////////////
+                                        Rendering
+    ///////////////////////////////////////////////////////////////////////////// */
+
+    /// Renders `value` (i.e. metadata, mismatches or failures) via `{:#?}`,
+    /// substituting labelled keys, see [`AddressBook::substitute`].
+    pub fn render(&self, value: &impl std::fmt::Debug) -> String {
+        self.address_book.substitute(&format!("{value:#?}"))
+    }
+
+    /// Substitutes labelled keys in each program log.
+    pub fn render_logs(&self, logs: &[String]) -> Vec<String> {
+        logs.iter()
+            .map(|log| self.address_book.substitute(log))
+            .collect()
+    }
+
+    /// Panics with the rendered `mismatches` (i.e. from
+    /// [`Self::execute_transaction_differential`]) if there are any.
+    #[track_caller]
+    pub fn assert_no_mismatches(&self, mismatches: &[Mismatch]) {
+        assert!(
+            mismatches.is_empty(),
+            "Unexpected mismatches; mismatches={}",
+            self.render(&mismatches)
+        );
+    }
+
     /* /////////////////////////////////////////////////////////////////////////////
                                         Chain
     ///////////////////////////////////////////////////////////////////////////// */
@@ -74,33 +167,360 @@ where
         self.inner.latest_blockhash()
     }
 
+    pub fn expire_blockhash(&mut self) {
+        self.inner.expire_blockhash()
+    }
+
+    /// Warps the [`Clock`] to `slot`.
+    pub fn warp_to_slot(&mut self, slot: Slot) {
+        self.inner.warp_to_slot(slot);
+    }
+
+    /// Warps the [`Clock`] to the first slot of `epoch`.
+    pub fn warp_to_epoch(&mut self, epoch: Epoch) {
+        let schedule = self.get_sysvar::<EpochSchedule>();
+        let mut clock = self.get_sysvar::<Clock>();
+        clock.slot = schedule.get_first_slot_in_epoch(epoch);
+        clock.epoch = epoch;
+        clock.leader_schedule_epoch = schedule.get_leader_schedule_epoch(clock.slot);
+        clock.epoch_start_timestamp = clock.unix_timestamp;
+
+        self.set_sysvar(&clock);
+    }
+
     /* /////////////////////////////////////////////////////////////////////////////
                                         Accounts
     ///////////////////////////////////////////////////////////////////////////// */
 
     pub fn load_program(&mut self, program_id: Pubkey, program_name: &str) {
         let elf = crate::utils::load_program_elf(program_name);
-        self.inner.add_program(&bpf_loader::ID, program_id, &elf);
+        self.add_program(program_id, &elf);
     }
 
+    pub fn add_program(&mut self, program_id: Pubkey, elf: &[u8]) {
+        self.inner.add_program(&bpf_loader::ID, program_id, elf);
+    }
+
+    /// Runs `processor` natively as the implementation of `program_id`, see
+    /// [`crate::native`].
+    #[cfg(feature = "native")]
+    pub fn add_native_program(&mut self, program_id: Pubkey, processor: ProcessInstruction) {
+        crate::native::register(program_id, processor);
+        self.inner
+            .add_builtin(program_id, crate::native::NativeProcessor::vm);
+    }
+
+    /// Runs `processor` natively, unless `TEST_SBF` is set in which case
+    /// `program_name` is loaded via [`Self::load_program`].
+    #[cfg(feature = "native")]
+    pub fn load_native_program(
+        &mut self,
+        program_id: Pubkey,
+        program_name: &str,
+        processor: ProcessInstruction,
+    ) {
+        match std::env::var_os("TEST_SBF") {
+            Some(_) => self.load_program(program_id, program_name),
+            None => self.add_native_program(program_id, processor),
+        }
+    }
+
+    /// Runs the ELF `program_name` as `program_id` with a GDB remote stub on
+    /// `port`, each invocation waits for a debugger to attach, see
+    /// [`crate::debugger`].
+    #[cfg(feature = "debugger")]
+    pub fn debug_program(&mut self, program_id: Pubkey, program_name: &str, port: u16) {
+        let elf = crate::utils::load_program_elf(program_name);
+        crate::debugger::register(program_id, elf, port);
+        self.inner
+            .add_builtin(program_id, crate::debugger::DebugProcessor::vm);
+    }
+
+    /// Loads every SBF program in the cargo workspace, see
+    /// [`crate::utils::workspace_program_id`].
+    pub fn load_workspace_programs(&mut self) -> Vec<(String, Pubkey)> {
+        let programs = crate::utils::workspace_programs();
+        for (program_name, program_id) in &programs {
+            self.load_program(*program_id, program_name);
+        }
+
+        programs
+    }
+
+    /// Deploys `program_name` via the upgradeable loader, with a `ProgramData`
+    /// account holding `upgrade_authority` & `deploy_slot`.
+    pub fn load_upgradeable_program(
+        &mut self,
+        program_id: Pubkey,
+        program_name: &str,
+        upgrade_authority: Option<Pubkey>,
+        deploy_slot: Slot,
+    ) {
+        let elf = crate::utils::load_program_elf(program_name);
+        self.add_upgradeable_program(program_id, &elf, upgrade_authority, deploy_slot);
+    }
+
+    pub fn add_upgradeable_program(
+        &mut self,
+        program_id: Pubkey,
+        elf: &[u8],
+        upgrade_authority: Option<Pubkey>,
+        deploy_slot: Slot,
+    ) {
+        let programdata_address = bpf_loader_upgradeable::get_program_data_address(&program_id);
+        let mut data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot: deploy_slot,
+            upgrade_authority_address: upgrade_authority,
+        })
+        .unwrap();
+        data.resize(UpgradeableLoaderState::size_of_programdata_metadata(), 0);
+        data.extend_from_slice(elf);
+        let program =
+            bincode::serialize(&UpgradeableLoaderState::Program { programdata_address }).unwrap();
+
+        // NB: ProgramData must exist before the program is added to the cache.
+        let rent = self.get_sysvar::<Rent>();
+        self.set(
+            programdata_address,
+            Account {
+                lamports: rent.minimum_balance(data.len()),
+                data,
+                owner: bpf_loader_upgradeable::ID,
+                ..Default::default()
+            },
+        );
+        self.set(
+            program_id,
+            Account {
+                lamports: rent.minimum_balance(program.len()),
+                data: program,
+                owner: bpf_loader_upgradeable::ID,
+                executable: true,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Replaces the ELF of an upgradeable program with `program_name`, keeping
+    /// the upgrade authority & setting the deploy slot to the current slot.
+    pub fn upgrade_program(&mut self, program_id: Pubkey, program_name: &str) {
+        let programdata_address = bpf_loader_upgradeable::get_program_data_address(&program_id);
+        let programdata = self.get_or_load(&programdata_address);
+        let Ok(UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. }) =
+            bincode::deserialize(&programdata.data)
+        else {
+            panic!(
+                "Not an upgradeable program; program_id={}",
+                self.address_book.render(&program_id)
+            );
+        };
+        assert!(
+            upgrade_authority_address.is_some(),
+            "Program is immutable; program_id={}",
+            self.address_book.render(&program_id)
+        );
+
+        let elf = crate::utils::load_program_elf(program_name);
+        let slot = self.get_sysvar::<Clock>().slot;
+        self.add_upgradeable_program(program_id, &elf, upgrade_authority_address, slot);
+    }
+
+    /// Swaps the ELF of a deployed upgradeable program (i.e. from a scenario)
+    /// for the local build of `program_name`.
+    ///
+    /// The program account, upgrade authority & deploy slot are left untouched,
+    /// the program does not need to have been touched yet.
+    pub fn override_program(&mut self, program_id: Pubkey, program_name: &str) {
+        let elf = crate::utils::load_program_elf(program_name);
+        self.override_program_elf(program_id, &elf);
+    }
+
+    /// Swaps the ELF of a deployed upgradeable program for `elf`, see
+    /// [`Self::override_program`].
+    pub fn override_program_elf(&mut self, program_id: Pubkey, elf: &[u8]) {
+        let program = self.get_or_load(&program_id);
+        assert_eq!(
+            program.owner,
+            bpf_loader_upgradeable::ID,
+            "Not an upgradeable program; program_id={}",
+            self.address_book.render(&program_id)
+        );
+        let programdata_address = bpf_loader_upgradeable::get_program_data_address(&program_id);
+        let mut programdata = self.get_or_load(&programdata_address);
+        let Ok(UpgradeableLoaderState::ProgramData { .. }) =
+            bincode::deserialize(&programdata.data)
+        else {
+            panic!("Missing program data; program_id={}", self.address_book.render(&program_id));
+        };
+
+        programdata
+            .data
+            .truncate(UpgradeableLoaderState::size_of_programdata_metadata());
+        programdata.data.extend_from_slice(elf);
+        programdata.lamports = programdata.lamports.max(
+            self.get_sysvar::<Rent>()
+                .minimum_balance(programdata.data.len()),
+        );
+
+        // NB: ProgramData must exist before the program is added to the cache.
+        self.set(programdata_address, programdata);
+        self.set(program_id, program);
+    }
+
+    /// Closes an upgradeable program as the loader's `Close` instruction would,
+    /// `ProgramData` is emptied (its lamports go to `recipient`) & reverts to
+    /// `Uninitialized` while the program account remains but can no longer be
+    /// invoked.
+    ///
+    /// As with the loader, the program must be mutable & cannot be closed in
+    /// its deploy slot (see [`Self::warp_to_slot`]).
+    pub fn close_program(&mut self, program_id: Pubkey, recipient: Pubkey) {
+        let program = self.get_or_load(&program_id);
+        assert_eq!(
+            program.owner,
+            bpf_loader_upgradeable::ID,
+            "Not an upgradeable program; program_id={}",
+            self.address_book.render(&program_id)
+        );
+        let programdata_address = bpf_loader_upgradeable::get_program_data_address(&program_id);
+        let mut programdata = self.get_or_load(&programdata_address);
+        let Ok(UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }) =
+            bincode::deserialize(&programdata.data)
+        else {
+            panic!("Missing program data; program_id={}", self.address_book.render(&program_id));
+        };
+        assert!(
+            upgrade_authority_address.is_some(),
+            "Program is immutable; program_id={}",
+            self.address_book.render(&program_id)
+        );
+        let clock = self.get_sysvar::<Clock>();
+        assert!(
+            slot != clock.slot,
+            "Cannot close a program in its deploy slot; program_id={}; slot={slot}",
+            self.address_book.render(&program_id)
+        );
+
+        // NB: Only the loader can tombstone the cached program, so we hand the
+        // upgrade authority to a throwaway keypair & close via the loader.
+        let authority = Keypair::new();
+        let header = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot,
+            upgrade_authority_address: Some(authority.pubkey()),
+        })
+        .unwrap();
+        programdata.data[..header.len()].copy_from_slice(&header);
+        self.set(programdata_address, programdata);
+        self.set(program_id, program);
+        self.set(authority.pubkey(), Account { lamports: 10u64.pow(9), ..Default::default() });
+
+        let ix = bpf_loader_upgradeable::close_any(
+            &programdata_address,
+            &recipient,
+            Some(&authority.pubkey()),
+            Some(&program_id),
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&authority.pubkey()),
+            &[&authority],
+            self.blockhash(),
+        );
+        self.inner.send_transaction(tx).unwrap_or_else(|err| {
+            panic!(
+                "Failed to close program; program_id={}; err={:?}",
+                self.address_book.render(&program_id),
+                err.err
+            )
+        });
+        self.set(authority.pubkey(), Account::default());
+    }
+
+    /// Loads the bundled ELF, see [`SplProgram::bundled_elf`].
     #[cfg(feature = "spl")]
     pub fn load_spl_program(&mut self, program: SplProgram) {
-        let (program_id, program_bytes) = match program {
-            SplProgram::Token => (spl_token::ID, litesvm::spl::TOKEN_ELF),
-            SplProgram::Token2022 => (spl_token_2022::ID, litesvm::spl::TOKEN_2022_ELF),
-            SplProgram::AssociatedTokenAccount => {
-                (spl_associated_token_account::ID, litesvm::spl::ASSOCIATED_TOKEN_ACCOUNT_ELF)
+        self.add_program(program.program_id(), program.bundled_elf());
+    }
+
+    /// Loads a pinned build of `program`, either bundled (see
+    /// [`SplProgram::bundled_version`]) or from `<name>-<version>.so`, i.e.
+    /// `tests/fixtures/spl_token_2022-6.0.0.so`.
+    #[cfg(feature = "spl")]
+    pub fn load_spl_program_version(&mut self, program: SplProgram, version: &str) {
+        match program.bundled_version(version) {
+            Some(elf) => self.add_program(program.program_id(), elf),
+            None => {
+                self.load_program(program.program_id(), &format!("{}-{version}", program.name()))
             }
-        };
+        }
+    }
 
-        self.inner
-            .add_program(&bpf_loader::ID, program_id, program_bytes);
+    /// Loads Token Metadata from `mpl_token_metadata.so`, i.e. as dumped via
+    /// `solana program dump metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s`.
+    #[cfg(feature = "metaplex")]
+    pub fn load_token_metadata_program(&mut self) {
+        self.load_program(mpl_token_metadata::ID, "mpl_token_metadata");
     }
 
     pub fn get(&self, key: &Pubkey) -> Option<Account> {
         self.inner.get_account(key)
     }
 
+    /// Returns the account if it has been touched, else asks the loader.
+    pub fn get_or_load(&self, key: &Pubkey) -> Account {
+        self.get(key).unwrap_or_else(|| self.loader.load(key))
+    }
+
+    pub fn get_as<T>(&self, key: &Pubkey) -> T::Value
+    where
+        T: AccountData,
+    {
+        let account = self.get_or_load(key);
+        assert!(
+            account.lamports > 0,
+            "Account does not exist; key={}",
+            self.address_book.render(key)
+        );
+
+        T::decode(&account.data)
+    }
+
+    /// Writes `value` to a rent-exempt account owned by `owner`.
+    pub fn set_as<T>(&mut self, key: Pubkey, owner: Pubkey, value: &T::Value)
+    where
+        T: AccountData,
+    {
+        let data = T::encode(value);
+        let lamports = self.get_sysvar::<Rent>().minimum_balance(data.len());
+
+        self.set(key, Account { lamports, data, owner, ..Default::default() });
+    }
+
+    /// Decodes the account, applies `modify` & writes it back in place.
+    ///
+    /// Any trailing bytes not covered by the encoding are left untouched &
+    /// lamports are topped up to remain rent-exempt.
+    pub fn modify<T>(&mut self, key: &Pubkey, modify: impl FnOnce(&mut T::Value))
+    where
+        T: AccountData,
+    {
+        let mut account = self.get_or_load(key);
+        let mut value = T::decode(&account.data);
+        modify(&mut value);
+
+        let encoded = T::encode(&value);
+        match encoded.len() <= account.data.len() {
+            true => account.data[..encoded.len()].copy_from_slice(&encoded),
+            false => account.data = encoded,
+        }
+        account.lamports = account.lamports.max(
+            self.get_sysvar::<Rent>()
+                .minimum_balance(account.data.len()),
+        );
+
+        self.set(*key, account);
+    }
+
     pub fn get_sysvar<T>(&self) -> T
     where
         T: Sysvar + SysvarId,
@@ -141,7 +561,254 @@ where
     ) -> Result<TransactionMetadata, FailedTransactionMetadata> {
         let tx = self.sanitize_and_load_accounts(tx.into());
 
-        self.inner.send_transaction(tx.to_versioned_transaction())
+        self.send_transaction(tx)
+    }
+
+    /// Executes `tx`, additionally capturing its [`Outcome`].
+    #[allow(clippy::result_large_err)]
+    pub fn execute_transaction_with_outcome(
+        &mut self,
+        tx: impl Into<VersionedTransaction>,
+    ) -> (Result<TransactionMetadata, FailedTransactionMetadata>, Outcome) {
+        let tx = self.sanitize_and_load_accounts(tx.into());
+        let writable: Vec<_> = tx
+            .message()
+            .account_keys()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| tx.message().is_writable(*i))
+            .map(|(_, key)| *key)
+            .collect();
+
+        let result = self.send_transaction(tx);
+        let accounts = writable
+            .into_iter()
+            .map(|key| (key, self.get(&key).unwrap_or_default()))
+            .collect();
+        let outcome = Outcome::new(&result, accounts);
+
+        (result, outcome)
+    }
+
+    /// Executes `tx` twice from the same state, first as is & then after
+    /// `setup` (i.e. swapping a program), returning the first result & any
+    /// mismatches between the two [`Outcome`]s.
+    ///
+    /// State is left as it was after the first execution.
+    #[allow(clippy::result_large_err)]
+    pub fn execute_transaction_differential(
+        &mut self,
+        tx: impl Into<VersionedTransaction>,
+        setup: impl FnOnce(&mut Self),
+    ) -> (Result<TransactionMetadata, FailedTransactionMetadata>, Vec<Mismatch>) {
+        let tx = tx.into();
+        let pre = self.checkpoint();
+        let (result, left) = self.execute_transaction_with_outcome(tx.clone());
+        let post = self.checkpoint();
+
+        self.restore(&pre);
+        setup(self);
+        let (_, right) = self.execute_transaction_with_outcome(tx);
+        self.restore(&post);
+
+        (result, left.diff(&right))
+    }
+
+    /// Executes `tx` with `processor` running natively as `program_id` & again
+    /// with the ELF `program_name`, see
+    /// [`Self::execute_transaction_differential`].
+    ///
+    /// Compute units are not compared, native processors only consume a
+    /// nominal amount.
+    #[cfg(feature = "native")]
+    #[allow(clippy::result_large_err)]
+    pub fn execute_transaction_native_vs_sbf(
+        &mut self,
+        tx: impl Into<VersionedTransaction>,
+        program_id: Pubkey,
+        program_name: &str,
+        processor: ProcessInstruction,
+    ) -> (Result<TransactionMetadata, FailedTransactionMetadata>, Vec<Mismatch>) {
+        self.add_native_program(program_id, processor);
+        let (result, mismatches) = self.execute_transaction_differential(tx, |svm| {
+            svm.load_program(program_id, program_name);
+        });
+
+        (
+            result,
+            mismatches
+                .into_iter()
+                .filter(|mismatch| !matches!(mismatch, Mismatch::ComputeUnits { .. }))
+                .collect(),
+        )
+    }
+
+    #[cfg(feature = "spl")]
+    #[allow(clippy::result_large_err)]
+    pub fn simulate_transaction_with_token_balances(
+        &mut self,
+        tx: impl Into<VersionedTransaction>,
+    ) -> (Result<SimulatedTransactionInfo, FailedTransactionMetadata>, TokenBalances) {
+        let tx = self.sanitize_and_load_accounts(tx.into());
+        let keys: Vec<_> = tx.message().account_keys().iter().copied().collect();
+
+        let pre = self.token_balances(&keys, |_| None);
+        let result = self
+            .inner
+            .simulate_transaction(tx.to_versioned_transaction());
+        let post = match &result {
+            Ok(info) => self.token_balances(&keys, |key| {
+                info.post_accounts
+                    .iter()
+                    .find(|(post_key, _)| post_key == key)
+                    .map(|(_, account)| account.clone().into())
+            }),
+            Err(_) => pre.clone(),
+        };
+
+        (result, TokenBalances { pre, post, address_book: self.address_book.clone() })
+    }
+
+    /// Executes `tx`, additionally returning the token balances of its
+    /// accounts, processed transactions reuse those recorded in the ledger.
+    #[cfg(all(feature = "spl", feature = "transaction-status"))]
+    #[allow(clippy::result_large_err)]
+    pub fn execute_transaction_with_token_balances(
+        &mut self,
+        tx: impl Into<VersionedTransaction>,
+    ) -> (Result<TransactionMetadata, FailedTransactionMetadata>, TokenBalances) {
+        let tx = self.sanitize_and_load_accounts(tx.into());
+        let keys: Vec<_> = tx.message().account_keys().iter().copied().collect();
+
+        let recorded = self.ledger.len();
+        let result = self.send_transaction(tx);
+        let (pre, post) = match self.ledger.entries().get(recorded) {
+            Some(entry) => (entry.pre_token_balances.clone(), entry.post_token_balances.clone()),
+            // NB: Rejected transactions leave the state untouched.
+            None => {
+                let balances = self.token_balances(&keys, |_| None);
+
+                (balances.clone(), balances)
+            }
+        };
+
+        (result, TokenBalances { pre, post, address_book: self.address_book.clone() })
+    }
+
+    /// Executes `tx`, additionally returning the token balances of its
+    /// accounts.
+    #[cfg(all(feature = "spl", not(feature = "transaction-status")))]
+    #[allow(clippy::result_large_err)]
+    pub fn execute_transaction_with_token_balances(
+        &mut self,
+        tx: impl Into<VersionedTransaction>,
+    ) -> (Result<TransactionMetadata, FailedTransactionMetadata>, TokenBalances) {
+        let tx = self.sanitize_and_load_accounts(tx.into());
+        let keys: Vec<_> = tx.message().account_keys().iter().copied().collect();
+
+        let pre = self.token_balances(&keys, |_| None);
+        let result = self.send_transaction(tx);
+        let post = self.token_balances(&keys, |_| None);
+
+        (result, TokenBalances { pre, post, address_book: self.address_book.clone() })
+    }
+
+    /// Parses the token balances of `keys`, accounts returned by `overrides`
+    /// take precedence over the current state.
+    ///
+    /// Only loaded accounts are considered, balances of token accounts whose
+    /// mint is not loaded are skipped (as `getTransaction` does).
+    #[cfg(feature = "spl")]
+    fn token_balances(
+        &self,
+        keys: &[Pubkey],
+        overrides: impl Fn(&Pubkey) -> Option<Account>,
+    ) -> Vec<TokenBalance> {
+        let account = |key: &Pubkey| overrides(key).or_else(|| self.get(key));
+
+        keys.iter()
+            .filter_map(|key| TokenBalance::parse(*key, &account(key)?, &account))
+            .collect()
+    }
+
+    /// Every transaction processed so far, see [`Ledger`].
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    /// Renders an executed transaction as `getTransaction` would, see
+    /// [`crate::transaction_status`].
+    #[cfg(feature = "transaction-status")]
+    pub fn get_transaction(
+        &self,
+        signature: &Signature,
+        encoding: UiTransactionEncoding,
+    ) -> Option<EncodedConfirmedTransactionWithStatusMeta> {
+        self.ledger
+            .get(signature)
+            .map(|entry| crate::transaction_status::encode_transaction(entry, encoding))
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn send_transaction(
+        &mut self,
+        tx: SanitizedTransaction,
+    ) -> Result<TransactionMetadata, FailedTransactionMetadata> {
+        let account_keys: Vec<_> = tx.message().account_keys().iter().copied().collect();
+        let balances = |svm: &Self| -> Vec<u64> {
+            account_keys
+                .iter()
+                .map(|key| svm.get(key).map_or(0, |account| account.lamports))
+                .collect()
+        };
+
+        let pre_balances = balances(self);
+        #[cfg(all(feature = "spl", feature = "transaction-status"))]
+        let pre_token_balances = self.token_balances(&account_keys, |_| None);
+        let transaction = tx.to_versioned_transaction();
+        let result = self.inner.send_transaction(transaction.clone());
+        let post_balances = balances(self);
+        #[cfg(all(feature = "spl", feature = "transaction-status"))]
+        let post_token_balances = self.token_balances(&account_keys, |_| None);
+
+        // NB: Transactions rejected before execution (i.e. already processed,
+        // unknown blockhash or failed sigverify) never make it into a block.
+        if let Err(FailedTransactionMetadata { err, .. }) = &result {
+            if !crate::ledger::is_processed(err) {
+                return result;
+            }
+        }
+
+        let (meta, err) = match &result {
+            Ok(meta) => (meta.clone(), None),
+            Err(FailedTransactionMetadata { err, meta }) => (meta.clone(), Some(err.clone())),
+        };
+        let clock = self.get_sysvar::<Clock>();
+        self.ledger.push(LedgerEntry {
+            signature: *tx.signature(),
+            slot: clock.slot,
+            block_time: clock.unix_timestamp,
+            loaded_addresses: match tx.message() {
+                SanitizedMessage::V0(message) => message.loaded_addresses.clone().into_owned(),
+                SanitizedMessage::Legacy(_) => LoadedAddresses::default(),
+            },
+            transaction,
+            account_keys,
+            meta,
+            err,
+            fee: pre_balances
+                .iter()
+                .sum::<u64>()
+                .saturating_sub(post_balances.iter().sum()),
+            pre_balances,
+            post_balances,
+            #[cfg(all(feature = "spl", feature = "transaction-status"))]
+            pre_token_balances,
+            #[cfg(all(feature = "spl", feature = "transaction-status"))]
+            post_token_balances,
+        });
+
+        result
     }
 
     fn sanitize_and_load_accounts(&mut self, tx: VersionedTransaction) -> SanitizedTransaction {
@@ -194,7 +861,11 @@ where
                     self.inner.set_account(exec_key, exec_data).unwrap();
                 }
                 (true, _) => {
-                    panic!("Unexpected program owner; program={key}; owner={}", account.owner)
+                    panic!(
+                        "Unexpected program owner; program={}; owner={}",
+                        self.address_book.render(key),
+                        self.address_book.render(&account.owner)
+                    )
                 }
                 (false, _) => {}
             }
@@ -206,3 +877,77 @@ where
         sanitized
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use solana_sdk::system_program;
+
+    use super::*;
+
+    struct Labelled(Pubkey);
+
+    impl AccountLoader for Labelled {
+        fn load(&self, _: &Pubkey) -> Account {
+            Account::default()
+        }
+
+        fn labels(&self) -> BTreeMap<Pubkey, String> {
+            BTreeMap::from([(self.0, "vault".to_owned())])
+        }
+    }
+
+    #[cfg(feature = "debugger")]
+    #[test]
+    fn debug_program_unregistered() {
+        use solana_sdk::instruction::{Instruction, InstructionError};
+        use solana_sdk::transaction::TransactionError;
+
+        use crate::utils::{test_payer_keypair, TEST_PAYER};
+
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        let memo = Pubkey::new_unique();
+        let debugged = Pubkey::new_unique();
+        svm.load_program(memo, "memo");
+        svm.inner
+            .add_builtin(debugged, crate::debugger::DebugProcessor::vm);
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        let tx = |svm: &Svm<DefaultLoader>, program_id: Pubkey| {
+            Transaction::new_signed_with_payer(
+                &[Instruction::new_with_bytes(program_id, b"hello", vec![])],
+                Some(&TEST_PAYER),
+                &[test_payer_keypair()],
+                svm.blockhash(),
+            )
+        };
+
+        // Programs that are not debugged run as usual.
+        svm.execute_transaction(tx(&svm, memo)).unwrap();
+
+        // Without a registered ELF the builtin fails instead of blocking.
+        let err = svm.execute_transaction(tx(&svm, debugged)).unwrap_err().err;
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::UnsupportedProgramId)
+        );
+    }
+
+    #[test]
+    fn render_loader_labels() {
+        let vault = Pubkey::new_unique();
+        let svm = Svm::new(Labelled(vault));
+
+        assert_eq!(
+            svm.render(&Mismatch::Balance { key: vault, left: 1, right: 2 }),
+            format!(
+                "Balance {{\n    key: vault ({}…),\n    left: 1,\n    right: 2,\n}}",
+                &vault.to_string()[..4]
+            )
+        );
+        assert_eq!(
+            svm.render_logs(&[format!("Program {} invoke [1]", system_program::ID)]),
+            ["Program system_program (1111…) invoke [1]"]
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use std::collections::BTreeMap;
 use std::env::VarError;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
-use std::sync::{OnceLock, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
 
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
@@ -14,7 +14,7 @@ use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
 use tokio::runtime::Runtime;
 
-use crate::utils::{read_json, read_json_gz, WriteOnDrop};
+use crate::utils::{executable_hash, read_json, read_json_gz, WriteOnDrop};
 
 pub fn test_data_path() -> PathBuf {
     // If `TEST_DATA` has been set, use that path.
@@ -36,21 +36,58 @@ pub fn test_data_path() -> PathBuf {
         .join("test-data")
 }
 
+/// The `TEST_RPC` url, if set scenarios are recorded from this RPC.
+pub fn test_rpc_url() -> Option<String> {
+    match std::env::var("TEST_RPC") {
+        Ok(url) => Some(url),
+        Err(VarError::NotPresent) => None,
+        Err(VarError::NotUnicode(raw)) => panic!("Non utf8 TEST_RPC; raw={raw:?}"),
+    }
+}
+
+/// Whether `TEST_ACCEPT_PROGRAM_CHANGES` is set, if not re-recording a
+/// program whose hash changed fails.
+pub fn accept_program_changes() -> bool {
+    std::env::var_os("TEST_ACCEPT_PROGRAM_CHANGES").is_some()
+}
+
 pub fn test_static_data_path() -> PathBuf {
     test_data_path().join("static.json")
 }
 
-static STATIC_CACHE: OnceLock<RpcCache> = OnceLock::new();
+static STATIC_CACHE: OnceLock<Arc<RpcCache>> = OnceLock::new();
 
 pub fn get_static_cache() -> &'static RpcCache {
-    STATIC_CACHE.get_or_init(|| read_json(&test_static_data_path()))
+    shared_static_cache()
+}
+
+fn shared_static_cache() -> &'static Arc<RpcCache> {
+    STATIC_CACHE.get_or_init(|| Arc::new(read_json(&test_static_data_path())))
+}
+
+/// The `static.json` cache of `dir`, shared if `dir` is [`test_data_path`].
+///
+/// Other directories (see [`TestRpc::load_scenario_from`]) may omit it.
+fn static_cache(dir: &Path) -> Arc<RpcCache> {
+    let path = dir.join("static.json");
+
+    match (dir == test_data_path(), path.exists()) {
+        (true, _) => shared_static_cache().clone(),
+        (false, true) => Arc::new(read_json(&path)),
+        (false, false) => Arc::default(),
+    }
 }
 
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct TestRpc {
-    static_cache: &'static RpcCache,
+    static_cache: Arc<RpcCache>,
     cache: RwLock<WriteOnDrop<RpcCache>>,
+    /// Hashes of the programs in the previous recording, used to flag drift.
+    recorded_hashes: ProgramHashes,
+    program_hashes: RwLock<WriteOnDrop<ProgramHashes>>,
+    /// Hand written labels from `{name}.labels.json`.
+    labels: Labels,
     /// If the RPC is set the cache file will be ignored & overwritten.
     #[derivative(Debug = "ignore")]
     rpc: Option<RpcClient>,
@@ -66,21 +103,32 @@ impl TestRpc {
 
 impl TestRpc {
     pub fn load_snapshot(slot: u64) -> Self {
-        let static_cache = get_static_cache();
+        let static_cache = shared_static_cache().clone();
         let cache_path = test_data_path().join(format!("snapshots/{slot}.json.gz"));
         let cache =
             RwLock::new(WriteOnDrop::new(read_json_gz::<RpcCache>(&cache_path), Some(cache_path)));
 
-        TestRpc { static_cache, cache, rpc: None }
+        TestRpc {
+            static_cache,
+            cache,
+            recorded_hashes: ProgramHashes::default(),
+            program_hashes: RwLock::new(WriteOnDrop::new(ProgramHashes::default(), None)),
+            labels: Labels::default(),
+            rpc: None,
+        }
     }
 
     pub fn load_scenario(name: &str) -> Self {
-        let cache_path = test_data_path().join(format!("{name}.json.gz"));
-        let rpc = match std::env::var("TEST_RPC") {
-            Ok(url) => Some(RpcClient::new(url)),
-            Err(VarError::NotPresent) => None,
-            Err(VarError::NotUnicode(raw)) => panic!("Non utf8 TEST_RPC; raw={raw:?}"),
-        };
+        TestRpc::load_scenario_from(&test_data_path(), name, test_rpc_url().as_deref())
+    }
+
+    /// Loads the scenario `name` from `dir`, recording it from `url` if set,
+    /// see [`test_data_path`] & [`test_rpc_url`].
+    pub fn load_scenario_from(dir: &Path, name: &str, url: Option<&str>) -> Self {
+        let cache_path = dir.join(format!("{name}.json.gz"));
+        let hashes_path = dir.join(format!("{name}.programs.json"));
+        let labels_path = dir.join(format!("{name}.labels.json"));
+        let rpc = url.map(|url| RpcClient::new(url.to_owned()));
 
         assert!(rpc.is_some() || cache_path.exists(), "Need either `TEST_RPC` or test cache file");
 
@@ -92,7 +140,36 @@ impl TestRpc {
             Some(cache_path),
         ));
 
-        TestRpc { static_cache: get_static_cache(), cache, rpc }
+        let recorded_hashes = match hashes_path.exists() {
+            true => read_json(&hashes_path),
+            false => ProgramHashes::default(),
+        };
+        let program_hashes = RwLock::new(WriteOnDrop::new(
+            match rpc.is_some() {
+                true => ProgramHashes::default(),
+                false => recorded_hashes.clone(),
+            },
+            Some(hashes_path),
+        ));
+
+        TestRpc {
+            static_cache: static_cache(dir),
+            cache,
+            recorded_hashes,
+            program_hashes,
+            labels: Labels::read(&labels_path),
+            rpc,
+        }
+    }
+
+    /// The labels of this scenario, see [`crate::address_book::AddressBook`].
+    pub fn labels(&self) -> BTreeMap<Pubkey, String> {
+        self.labels.0.clone()
+    }
+
+    /// The [`executable_hash`] of every program recorded by this scenario.
+    pub fn program_hashes(&self) -> BTreeMap<Pubkey, String> {
+        self.program_hashes.read().unwrap().0.clone()
     }
 
     pub fn account_sync(&self, runtime: &'static Runtime, key: &Pubkey) -> Account {
@@ -125,6 +202,28 @@ impl TestRpc {
             .value
             .unwrap_or_default();
 
+        // Record program hashes.
+        if let Some(hash) = executable_hash(&account) {
+            if let Some(previous) = self
+                .recorded_hashes
+                .0
+                .get(key)
+                .filter(|prev| **prev != hash)
+            {
+                assert!(
+                    accept_program_changes(),
+                    "Program changed since last recording, set `TEST_ACCEPT_PROGRAM_CHANGES` to \
+                     re-record; key={key}; previous={previous}; hash={hash}"
+                );
+                eprintln!(
+                    "Program changed since last recording; key={key}; previous={previous}; \
+                     hash={hash}"
+                );
+            }
+
+            self.program_hashes.write().unwrap().0.insert(*key, hash);
+        }
+
         // Update cache.
         self.cache.write().unwrap().insert(*key, account.clone());
 
@@ -152,3 +251,45 @@ impl DerefMut for RpcCache {
         &mut self.0
     }
 }
+
+/// [`executable_hash`] of every program recorded by a scenario.
+#[serde_as]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ProgramHashes(
+    #[serde_as(as = "BTreeMap<serde_with::DisplayFromStr, _>")] pub BTreeMap<Pubkey, String>,
+);
+
+/// Pubkey labels of a scenario, see [`crate::address_book::AddressBook`].
+#[serde_as]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Labels(
+    #[serde_as(as = "BTreeMap<serde_with::DisplayFromStr, _>")] pub BTreeMap<Pubkey, String>,
+);
+
+impl Labels {
+    /// Reads a `{name}.labels.json` file, empty if it does not exist.
+    fn read(path: &Path) -> Self {
+        match path.exists() {
+            true => read_json(path),
+            false => Labels::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_labels() {
+        let dir = std::env::temp_dir().join(format!("svm-test-labels-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let vault = Pubkey::new_unique();
+        let path = dir.join("swap.labels.json");
+        std::fs::write(&path, format!(r#"{{ "{vault}": "vault" }}"#)).unwrap();
+
+        assert_eq!(Labels::read(&path).0, BTreeMap::from([(vault, "vault".to_owned())]));
+        assert_eq!(Labels::read(&dir.join("missing.labels.json")).0, BTreeMap::default());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
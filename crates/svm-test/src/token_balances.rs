@@ -0,0 +1,203 @@
+use derivative::Derivative;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::address_book::AddressBook;
+
+/// The balance of a single token account (for either token program), mirrors
+/// `preTokenBalances`/`postTokenBalances` in RPC transaction metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub program_id: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenBalance {
+    /// Parses `account` as a token account, the mint is resolved via `mint`
+    /// to determine the decimals.
+    pub fn parse(
+        key: Pubkey,
+        account: &Account,
+        mint: impl FnOnce(&Pubkey) -> Option<Account>,
+    ) -> Option<Self> {
+        if account.owner != spl_token::ID && account.owner != spl_token_2022::ID {
+            return None;
+        }
+
+        let token =
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).ok()?;
+        let mint_account = mint(&token.base.mint)?;
+        let mint =
+            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_account.data).ok()?;
+
+        Some(TokenBalance {
+            account: key,
+            mint: token.base.mint,
+            owner: token.base.owner,
+            program_id: account.owner,
+            amount: token.base.amount,
+            decimals: mint.base.decimals,
+        })
+    }
+
+    pub fn ui_amount_string(&self) -> String {
+        spl_token::amount_to_ui_amount_string_trimmed(self.amount, self.decimals)
+    }
+}
+
+/// Token balances before & after a transaction.
+#[derive(Derivative, Clone, Default)]
+#[derivative(Debug, PartialEq, Eq)]
+pub struct TokenBalances {
+    pub pre: Vec<TokenBalance>,
+    pub post: Vec<TokenBalance>,
+    /// Labels used by [`Self::changes`] & the assertions, the executing
+    /// [`crate::Svm::address_book`].
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub address_book: AddressBook,
+}
+
+impl TokenBalances {
+    /// The change in balance of a single token account.
+    pub fn account_delta(&self, account: &Pubkey) -> i128 {
+        Self::sum(&self.post, |balance| &balance.account == account)
+            - Self::sum(&self.pre, |balance| &balance.account == account)
+    }
+
+    /// The change in balance across all of `owner`'s token accounts for `mint`.
+    pub fn owner_delta(&self, owner: &Pubkey, mint: &Pubkey) -> i128 {
+        let filter = |balance: &TokenBalance| &balance.owner == owner && &balance.mint == mint;
+
+        Self::sum(&self.post, filter) - Self::sum(&self.pre, filter)
+    }
+
+    /// Renders each changed account as `account (owner): pre -> post mint`
+    /// using UI amounts & keys rendered via [`Self::address_book`].
+    pub fn changes(&self) -> Vec<String> {
+        let mut accounts: Vec<_> = self
+            .pre
+            .iter()
+            .chain(&self.post)
+            .map(|balance| balance.account)
+            .collect();
+        accounts.sort();
+        accounts.dedup();
+
+        accounts
+            .into_iter()
+            .filter(|account| self.account_delta(account) != 0)
+            .map(|account| {
+                let pre = self.pre.iter().find(|balance| balance.account == account);
+                let post = self.post.iter().find(|balance| balance.account == account);
+                let any = pre.or(post).unwrap();
+
+                format!(
+                    "{} ({}): {} -> {} {}",
+                    self.address_book.render(&account),
+                    self.address_book.render(&any.owner),
+                    pre.map(TokenBalance::ui_amount_string)
+                        .unwrap_or_else(|| "0".to_owned()),
+                    post.map(TokenBalance::ui_amount_string)
+                        .unwrap_or_else(|| "0".to_owned()),
+                    self.address_book.render(&any.mint),
+                )
+            })
+            .collect()
+    }
+
+    #[track_caller]
+    pub fn assert_received(&self, owner: &Pubkey, mint: &Pubkey, amount: u64) {
+        assert_eq!(
+            self.owner_delta(owner, mint),
+            i128::from(amount),
+            "Unexpected balance change; owner={}; mint={}; changes={:#?}",
+            self.address_book.render(owner),
+            self.address_book.render(mint),
+            self.changes(),
+        );
+    }
+
+    #[track_caller]
+    pub fn assert_sent(&self, owner: &Pubkey, mint: &Pubkey, amount: u64) {
+        assert_eq!(
+            self.owner_delta(owner, mint),
+            -i128::from(amount),
+            "Unexpected balance change; owner={}; mint={}; changes={:#?}",
+            self.address_book.render(owner),
+            self.address_book.render(mint),
+            self.changes(),
+        );
+    }
+
+    fn sum(balances: &[TokenBalance], filter: impl Fn(&TokenBalance) -> bool) -> i128 {
+        balances
+            .iter()
+            .filter(|balance| filter(balance))
+            .map(|balance| i128::from(balance.amount))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signer::Signer;
+    use solana_sdk::transaction::Transaction;
+
+    use super::*;
+    use crate::spl::SplProgram;
+    use crate::svm::DefaultLoader;
+    use crate::utils::spl_token::{mock_ata, mock_mint, MintBuilder};
+    use crate::utils::{keypair, test_payer_keypair, TEST_PAYER};
+    use crate::Svm;
+
+    #[test]
+    fn transfer() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        svm.load_spl_program(SplProgram::Token);
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        let alice = keypair("alice");
+        let transfer = |svm: &mut Svm<DefaultLoader>, mint: Pubkey| {
+            let from = mock_ata(svm, mint, alice.pubkey(), 5 * 10u64.pow(6));
+            let to = mock_ata(svm, mint, TEST_PAYER, 0);
+            let ix = spl_token::instruction::transfer(
+                &spl_token::ID,
+                &from,
+                &to,
+                &alice.pubkey(),
+                &[],
+                10u64.pow(6),
+            )
+            .unwrap();
+            let tx = Transaction::new_signed_with_payer(
+                &[ix],
+                Some(&TEST_PAYER),
+                &[test_payer_keypair(), &alice],
+                svm.blockhash(),
+            );
+            let (result, balances) = svm.execute_transaction_with_token_balances(tx);
+            result.unwrap();
+
+            balances
+        };
+
+        let mint = mock_mint(&mut svm, Pubkey::new_unique(), &MintBuilder::new(6));
+        let balances = transfer(&mut svm, mint);
+        balances.assert_sent(&alice.pubkey(), &mint, 10u64.pow(6));
+        balances.assert_received(&TEST_PAYER, &mint, 10u64.pow(6));
+        assert_eq!(balances.changes().len(), 2);
+
+        // Changes are rendered with the svm's labels.
+        svm.address_book.insert(mint, "TEST");
+        let balances = transfer(&mut svm, mint);
+        assert!(balances.changes()[0].ends_with(&format!("TEST ({}…)", &mint.to_string()[..4])));
+
+        // Balances are skipped when the mint is not loaded.
+        let balances = transfer(&mut svm, Pubkey::new_unique());
+        assert_eq!(balances, TokenBalances::default());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::ops::Deref;
 
 use auto_impl::auto_impl;
@@ -40,6 +41,12 @@ pub trait AccountLoader {
     fn load_multiple(&self, keys: &[Pubkey]) -> Vec<Account> {
         keys.iter().map(|key| self.load(key)).collect()
     }
+
+    /// Labels added to [`crate::Svm::address_book`] when an [`crate::Svm`] is
+    /// created with this loader, i.e. [`crate::Scenario::labels`].
+    fn labels(&self) -> BTreeMap<Pubkey, String> {
+        BTreeMap::default()
+    }
 }
 
 impl<S> AccountLoader for std::collections::HashMap<Pubkey, Account, S>
@@ -51,7 +58,7 @@ where
     }
 }
 
-impl AccountLoader for std::collections::BTreeMap<Pubkey, Account> {
+impl AccountLoader for BTreeMap<Pubkey, Account> {
     fn load(&self, key: &Pubkey) -> Account {
         self.get(key).cloned().unwrap_or_default()
     }
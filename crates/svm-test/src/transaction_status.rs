@@ -0,0 +1,155 @@
+//! Renders [`LedgerEntry`]s as `getTransaction` would, see
+//! [`crate::Svm::get_transaction`].
+#[cfg(feature = "spl")]
+use solana_account_decoder::parse_token::UiTokenAmount;
+#[cfg(feature = "spl")]
+use solana_transaction_status::TransactionTokenBalance;
+use solana_transaction_status::{
+    map_inner_instructions, ConfirmedTransactionWithStatusMeta,
+    EncodedConfirmedTransactionWithStatusMeta, TransactionStatusMeta, TransactionWithStatusMeta,
+    UiTransactionEncoding, VersionedTransactionWithStatusMeta,
+};
+
+use crate::ledger::LedgerEntry;
+#[cfg(feature = "spl")]
+use crate::token_balances::TokenBalance;
+
+/// Converts `entry` into the status meta stored by an RPC node.
+pub fn confirmed_transaction(entry: &LedgerEntry) -> ConfirmedTransactionWithStatusMeta {
+    let return_data = &entry.meta.return_data;
+
+    ConfirmedTransactionWithStatusMeta {
+        slot: entry.slot,
+        block_time: Some(entry.block_time),
+        tx_with_meta: TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta {
+            transaction: entry.transaction.clone(),
+            meta: TransactionStatusMeta {
+                status: entry.err.clone().map_or(Ok(()), Err),
+                fee: entry.fee,
+                pre_balances: entry.pre_balances.clone(),
+                post_balances: entry.post_balances.clone(),
+                inner_instructions: Some(
+                    map_inner_instructions(entry.meta.inner_instructions.clone()).collect(),
+                ),
+                log_messages: Some(entry.meta.logs.clone()),
+                #[cfg(feature = "spl")]
+                pre_token_balances: Some(token_balances(entry, &entry.pre_token_balances)),
+                #[cfg(feature = "spl")]
+                post_token_balances: Some(token_balances(entry, &entry.post_token_balances)),
+                #[cfg(not(feature = "spl"))]
+                pre_token_balances: None,
+                #[cfg(not(feature = "spl"))]
+                post_token_balances: None,
+                rewards: Some(Vec::default()),
+                loaded_addresses: entry.loaded_addresses.clone(),
+                return_data: (!return_data.data.is_empty()).then(|| return_data.clone()),
+                compute_units_consumed: Some(entry.meta.compute_units_consumed),
+            },
+        }),
+    }
+}
+
+/// Encodes `entry` as returned by `getTransaction` with `encoding` (json,
+/// jsonParsed, base58 or base64).
+pub fn encode_transaction(
+    entry: &LedgerEntry,
+    encoding: UiTransactionEncoding,
+) -> EncodedConfirmedTransactionWithStatusMeta {
+    confirmed_transaction(entry)
+        .encode(encoding, Some(0))
+        .unwrap_or_else(|err| {
+            panic!("Failed to encode transaction; signature={}; err={err}", entry.signature)
+        })
+}
+
+#[cfg(feature = "spl")]
+fn token_balances(entry: &LedgerEntry, balances: &[TokenBalance]) -> Vec<TransactionTokenBalance> {
+    balances
+        .iter()
+        .map(|balance| TransactionTokenBalance {
+            account_index: entry
+                .account_keys
+                .iter()
+                .position(|key| key == &balance.account)
+                .unwrap() as u8,
+            mint: balance.mint.to_string(),
+            ui_token_amount: UiTokenAmount {
+                ui_amount: Some(spl_token::amount_to_ui_amount(balance.amount, balance.decimals)),
+                decimals: balance.decimals,
+                amount: balance.amount.to_string(),
+                ui_amount_string: balance.ui_amount_string(),
+            },
+            owner: balance.owner.to_string(),
+            program_id: balance.program_id.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::account::Account;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::Transaction;
+    use solana_transaction_status::option_serializer::OptionSerializer;
+    use solana_transaction_status::EncodedTransaction;
+
+    use super::*;
+    use crate::svm::DefaultLoader;
+    use crate::utils::{test_payer_keypair, TEST_PAYER};
+    use crate::Svm;
+
+    #[test]
+    fn system_transfer() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        let tx = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&TEST_PAYER, &Pubkey::new_unique(), 1)],
+            Some(&TEST_PAYER),
+            &[test_payer_keypair()],
+            svm.blockhash(),
+        );
+        let signature = svm.execute_transaction(tx).unwrap().signature;
+
+        let encoded = svm
+            .get_transaction(&signature, UiTransactionEncoding::JsonParsed)
+            .unwrap();
+        let meta = encoded.transaction.meta.unwrap();
+        assert_eq!(meta.fee, 5000);
+        assert_eq!(meta.pre_balances[0] - meta.post_balances[0], 5001);
+        assert!(matches!(meta.log_messages, OptionSerializer::Some(logs) if logs.len() == 2));
+        assert!(matches!(encoded.transaction.transaction, EncodedTransaction::Json(_)));
+
+        let encoded = svm
+            .get_transaction(&signature, UiTransactionEncoding::Base64)
+            .unwrap();
+        assert!(matches!(encoded.transaction.transaction, EncodedTransaction::Binary(..)));
+    }
+
+    #[test]
+    fn priority_fee() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+                ComputeBudgetInstruction::set_compute_unit_price(10u64.pow(6)),
+                system_instruction::transfer(&TEST_PAYER, &Pubkey::new_unique(), 1),
+            ],
+            Some(&TEST_PAYER),
+            &[test_payer_keypair()],
+            svm.blockhash(),
+        );
+        let signature = svm.execute_transaction(tx).unwrap().signature;
+
+        // The recorded fee is the fee charged, not only the signature fee.
+        let meta = svm
+            .get_transaction(&signature, UiTransactionEncoding::Json)
+            .unwrap()
+            .transaction
+            .meta
+            .unwrap();
+        assert_eq!(meta.fee, meta.pre_balances[0] - meta.post_balances[0] - 1);
+    }
+}
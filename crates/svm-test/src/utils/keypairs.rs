@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::{SeedDerivable, Signer};
+
+use crate::address_book::AddressBook;
+use crate::{AccountLoader, Svm};
+
+static LABELS: OnceLock<RwLock<HashMap<Pubkey, String>>> = OnceLock::new();
+
+/// A keypair derived from `label` (seeded with `sha256(label)`), the label is
+/// remembered for rendering, see [`labelled`].
+pub fn keypair(label: &str) -> Keypair {
+    let keypair = Keypair::from_seed(&solana_sdk::hash::hash(label.as_bytes()).to_bytes())
+        .unwrap_or_else(|err| panic!("Failed to derive keypair; label={label}; err={err}"));
+    set_label(keypair.pubkey(), label);
+
+    keypair
+}
+
+/// Derives [`keypair`] & funds it with `lamports` of SOL.
+pub fn funded_keypair<L>(svm: &mut Svm<L>, label: &str, lamports: u64) -> Keypair
+where
+    L: AccountLoader,
+{
+    let keypair = keypair(label);
+    svm.set(keypair.pubkey(), Account { lamports, ..Default::default() });
+
+    keypair
+}
+
+pub fn set_label(key: Pubkey, label: &str) {
+    LABELS
+        .get_or_init(Default::default)
+        .write()
+        .unwrap()
+        .insert(key, label.to_owned());
+}
+
+pub fn label(key: &Pubkey) -> Option<String> {
+    LABELS.get()?.read().unwrap().get(key).cloned()
+}
+
+/// Every label registered via [`keypair`] or [`set_label`].
+pub fn labels() -> Vec<(Pubkey, String)> {
+    LABELS.get().map_or_else(Vec::default, |labels| {
+        labels
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(key, label)| (*key, label.clone()))
+            .collect()
+    })
+}
+
+/// Renders `key` as `alice (AKnL…)` if labelled, else as base58, see
+/// [`AddressBook::well_known`].
+pub fn labelled(key: &Pubkey) -> String {
+    AddressBook::well_known().render(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alice() {
+        let alice = keypair("alice");
+        assert_eq!(alice.pubkey(), keypair("alice").pubkey());
+        assert_ne!(alice.pubkey(), keypair("bob").pubkey());
+        assert_eq!(
+            labelled(&alice.pubkey()),
+            format!("alice ({}…)", &alice.pubkey().to_string()[..4])
+        );
+    }
+}
@@ -7,6 +7,7 @@ use itertools::Itertools;
 pub fn load_program_elf(program_name: &str) -> Vec<u8> {
     let file_name = format!("{program_name}.so");
     let file_path = find_file(&file_name);
+    let file_path = super::check_stale_elf(program_name, &file_path).unwrap_or(file_path);
 
     std::fs::read(&file_path)
         .unwrap_or_else(|err| panic!("Failed to read program file; path={file_path:?}; err={err}"))
@@ -0,0 +1,256 @@
+//! Metaplex Token Metadata accounts.
+//!
+//! The mint itself is not written, NFT mints are expected to have the master
+//! edition PDA as their mint & freeze authority.
+use mpl_token_metadata::accounts::{MasterEdition, Metadata, TokenRecord};
+use mpl_token_metadata::types::{
+    Collection, CollectionDetails, Creator, Key, ProgrammableConfig, TokenDelegateRole,
+    TokenStandard, TokenState,
+};
+use mpl_token_metadata::{MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+
+use crate::{AccountLoader, Svm};
+
+/// Metadata accounts are allocated at their max size.
+const MAX_METADATA_LEN: usize = 679;
+/// Master edition accounts are allocated at their max size.
+const MAX_MASTER_EDITION_LEN: usize = 282;
+
+/// Writes the metadata PDA of `mint`, returning its address.
+pub fn mock_metadata<L>(svm: &mut Svm<L>, mint: Pubkey, metadata: &MetadataBuilder) -> Pubkey
+where
+    L: AccountLoader,
+{
+    let key = Metadata::find_pda(&mint).0;
+    svm.set(key, metadata.build(mint));
+
+    key
+}
+
+/// Writes the master edition PDA of `mint`, returning its address.
+pub fn mock_master_edition<L>(svm: &mut Svm<L>, mint: Pubkey, max_supply: Option<u64>) -> Pubkey
+where
+    L: AccountLoader,
+{
+    let key = MasterEdition::find_pda(&mint).0;
+    let edition = MasterEdition { key: Key::MasterEditionV2, supply: 0, max_supply };
+    svm.set(key, program_account(to_vec(&edition), MAX_MASTER_EDITION_LEN));
+
+    key
+}
+
+/// Writes the token record PDA of a programmable NFT's `token` account,
+/// returning its address.
+pub fn mock_token_record<L>(
+    svm: &mut Svm<L>,
+    mint: Pubkey,
+    token: Pubkey,
+    state: TokenState,
+    delegate: Option<(Pubkey, TokenDelegateRole)>,
+) -> Pubkey
+where
+    L: AccountLoader,
+{
+    let (key, bump) = TokenRecord::find_pda(&mint, &token);
+    let (delegate, delegate_role) = delegate.unzip();
+    let record = TokenRecord {
+        key: Key::TokenRecord,
+        bump,
+        state,
+        rule_set_revision: None,
+        delegate,
+        delegate_role,
+        locked_transfer: None,
+    };
+    svm.set(key, program_account(to_vec(&record), TokenRecord::LEN));
+
+    key
+}
+
+#[derive(Debug, Clone)]
+pub struct MetadataBuilder {
+    update_authority: Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    creators: Vec<Creator>,
+    primary_sale_happened: bool,
+    is_mutable: bool,
+    token_standard: TokenStandard,
+    collection: Option<Collection>,
+    collection_details: Option<CollectionDetails>,
+    programmable_config: Option<ProgrammableConfig>,
+}
+
+impl MetadataBuilder {
+    pub fn new(update_authority: Pubkey, name: &str, symbol: &str, uri: &str) -> Self {
+        MetadataBuilder {
+            update_authority,
+            name: name.to_owned(),
+            symbol: symbol.to_owned(),
+            uri: uri.to_owned(),
+            seller_fee_basis_points: 0,
+            creators: Vec::default(),
+            primary_sale_happened: false,
+            is_mutable: true,
+            token_standard: TokenStandard::NonFungible,
+            collection: None,
+            collection_details: None,
+            programmable_config: None,
+        }
+    }
+
+    pub fn seller_fee_basis_points(mut self, seller_fee_basis_points: u16) -> Self {
+        self.seller_fee_basis_points = seller_fee_basis_points;
+
+        self
+    }
+
+    pub fn creator(mut self, address: Pubkey, verified: bool, share: u8) -> Self {
+        self.creators.push(Creator { address, verified, share });
+
+        self
+    }
+
+    pub fn primary_sale_happened(mut self) -> Self {
+        self.primary_sale_happened = true;
+
+        self
+    }
+
+    pub fn immutable(mut self) -> Self {
+        self.is_mutable = false;
+
+        self
+    }
+
+    pub fn token_standard(mut self, token_standard: TokenStandard) -> Self {
+        self.token_standard = token_standard;
+
+        self
+    }
+
+    pub fn collection(mut self, key: Pubkey, verified: bool) -> Self {
+        self.collection = Some(Collection { verified, key });
+
+        self
+    }
+
+    /// Marks this NFT as a sized collection.
+    pub fn collection_size(mut self, size: u64) -> Self {
+        self.collection_details = Some(CollectionDetails::V1 { size });
+
+        self
+    }
+
+    /// Makes this a programmable NFT, optionally enforcing `rule_set`.
+    pub fn programmable(mut self, rule_set: Option<Pubkey>) -> Self {
+        self.token_standard = TokenStandard::ProgrammableNonFungible;
+        self.programmable_config = Some(ProgrammableConfig::V1 { rule_set });
+
+        self
+    }
+
+    pub fn build(&self, mint: Pubkey) -> Account {
+        let metadata = Metadata {
+            key: Key::MetadataV1,
+            update_authority: self.update_authority,
+            mint,
+            name: puff(&self.name, MAX_NAME_LENGTH),
+            symbol: puff(&self.symbol, MAX_SYMBOL_LENGTH),
+            uri: puff(&self.uri, MAX_URI_LENGTH),
+            seller_fee_basis_points: self.seller_fee_basis_points,
+            creators: (!self.creators.is_empty()).then(|| self.creators.clone()),
+            primary_sale_happened: self.primary_sale_happened,
+            is_mutable: self.is_mutable,
+            edition_nonce: Some(MasterEdition::find_pda(&mint).1),
+            token_standard: Some(self.token_standard.clone()),
+            collection: self.collection.clone(),
+            uses: None,
+            collection_details: self.collection_details.clone(),
+            programmable_config: self.programmable_config.clone(),
+        };
+
+        program_account(to_vec(&metadata), MAX_METADATA_LEN)
+    }
+}
+
+/// Pads `value` to `len` bytes with null bytes, as done by the program on
+/// creation.
+fn puff(value: &str, len: usize) -> String {
+    assert!(value.len() <= len, "Value too long; value={value}; max={len}");
+
+    String::from_utf8(
+        value
+            .bytes()
+            .chain(std::iter::repeat(0))
+            .take(len)
+            .collect(),
+    )
+    .unwrap()
+}
+
+fn to_vec(value: &impl borsh010::BorshSerialize) -> Vec<u8> {
+    borsh010::to_vec(value).unwrap()
+}
+
+fn program_account(mut data: Vec<u8>, len: usize) -> Account {
+    data.resize(data.len().max(len), 0);
+
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: mpl_token_metadata::ID,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svm::DefaultLoader;
+
+    #[test]
+    fn programmable_nft() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        let mint = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let metadata = mock_metadata(
+            &mut svm,
+            mint,
+            &MetadataBuilder::new(authority, "Test", "TST", "https://example.com")
+                .creator(authority, true, 100)
+                .programmable(None),
+        );
+        let edition = mock_master_edition(&mut svm, mint, Some(0));
+        mock_token_record(&mut svm, mint, token, TokenState::Unlocked, None);
+
+        let account = svm.get(&metadata).unwrap();
+        assert_eq!(account.data.len(), MAX_METADATA_LEN);
+        let metadata = Metadata::from_bytes(&account.data).unwrap();
+        assert_eq!(metadata.name.trim_end_matches('\0'), "Test");
+        assert_eq!(metadata.token_standard, Some(TokenStandard::ProgrammableNonFungible));
+        assert_eq!(
+            MasterEdition::from_bytes(&svm.get(&edition).unwrap().data)
+                .unwrap()
+                .max_supply,
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn pads_by_bytes() {
+        let metadata = MetadataBuilder::new(Pubkey::new_unique(), "Tëst", "TST", "")
+            .build(Pubkey::new_unique());
+        let metadata = Metadata::from_bytes(&metadata.data).unwrap();
+
+        assert_eq!(metadata.name.len(), MAX_NAME_LENGTH);
+        assert_eq!(metadata.name.trim_end_matches('\0'), "Tëst");
+    }
+}
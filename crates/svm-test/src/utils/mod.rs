@@ -1,15 +1,28 @@
+mod keypairs;
 mod load_elf;
 mod locate_manifest;
+#[cfg(feature = "metaplex")]
+pub mod metaplex;
+#[cfg(feature = "oracle")]
+pub mod oracle;
 mod pack_to_vec;
+mod program_hash;
 mod read_json_gz;
 #[cfg(feature = "spl")]
 pub mod spl_token;
+#[cfg(feature = "spl")]
+pub mod spl_token_2022;
+pub mod stake;
 mod test_payer;
+mod workspace;
 mod write_on_drop;
 
+pub use keypairs::*;
 pub use load_elf::*;
 pub(crate) use locate_manifest::*;
 pub use pack_to_vec::*;
+pub use program_hash::*;
 pub use read_json_gz::*;
 pub use test_payer::*;
+pub use workspace::*;
 pub use write_on_drop::*;
@@ -0,0 +1,295 @@
+//! Pyth & Switchboard price accounts.
+//!
+//! Supports Pyth legacy (push) price accounts, Pyth receiver `PriceUpdateV2`
+//! accounts & Switchboard on-demand pull feeds. Existing accounts (i.e. from a
+//! scenario) are edited in place, leaving all unrelated fields untouched.
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+
+use crate::{AccountLoader, ScenarioWithOverrides, Svm};
+
+pub const PYTH_ORACLE_PROGRAM: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+pub const PYTH_RECEIVER_PROGRAM: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM: Pubkey =
+    pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_LEGACY_LEN: usize = 3312;
+const PYTH_PULL_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+const PYTH_PULL_LEN: usize = 134;
+const SWITCHBOARD_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+const SWITCHBOARD_LEN: usize = 3208;
+/// Switchboard values are fixed point with 18 decimals.
+const SWITCHBOARD_PRECISION: i32 = 18;
+
+/// A price of `price * 10^expo` with a confidence interval of `conf * 10^expo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+    pub slot: u64,
+}
+
+impl OraclePrice {
+    /// A price published at the current slot & timestamp of `clock`.
+    pub fn new(price: i64, conf: u64, expo: i32, clock: &Clock) -> Self {
+        OraclePrice { price, conf, expo, publish_time: clock.unix_timestamp, slot: clock.slot }
+    }
+}
+
+/// Loads an existing oracle account, sets its price as of the current
+/// [`Clock`] & writes it back.
+pub fn mock_price<L>(svm: &mut Svm<L>, key: Pubkey, price: i64, conf: u64, expo: i32) -> Pubkey
+where
+    L: AccountLoader,
+{
+    let mut account = svm.get_or_load(&key);
+    set_price(&mut account, &OraclePrice::new(price, conf, expo, &svm.get_sysvar()));
+    svm.set(key, account);
+
+    key
+}
+
+/// Same as [`mock_price`] but writes the updated account to the scenario's
+/// overrides.
+pub fn override_price(scenario: &mut ScenarioWithOverrides, key: Pubkey, price: &OraclePrice) {
+    let mut account = scenario.load(&key);
+    set_price(&mut account, price);
+
+    scenario.overrides.insert(key, account);
+}
+
+/// Reads the price of any supported oracle account.
+///
+/// Switchboard values are scaled to the highest precision that fits an `i64`.
+pub fn read_price(account: &Account) -> OraclePrice {
+    let data = &account.data;
+    match Layout::detect(account) {
+        Layout::PythLegacy => OraclePrice {
+            price: i64::from_le_bytes(read(data, 208)),
+            conf: u64::from_le_bytes(read(data, 216)),
+            expo: i32::from_le_bytes(read(data, 20)),
+            publish_time: i64::from_le_bytes(read(data, 96)),
+            slot: u64::from_le_bytes(read(data, 232)),
+        },
+        Layout::PythPull(message) => OraclePrice {
+            price: i64::from_le_bytes(read(data, message + 32)),
+            conf: u64::from_le_bytes(read(data, message + 40)),
+            expo: i32::from_le_bytes(read(data, message + 48)),
+            publish_time: i64::from_le_bytes(read(data, message + 52)),
+            slot: u64::from_le_bytes(read(data, message + 84)),
+        },
+        Layout::Switchboard => {
+            let value = i128::from_le_bytes(read(data, 8 + 2256));
+            let std_dev = i128::from_le_bytes(read(data, 8 + 2256 + 16));
+            let expo = (-SWITCHBOARD_PRECISION..=0)
+                .find(|expo| i64::try_from(scale(value, -SWITCHBOARD_PRECISION, *expo)).is_ok())
+                .unwrap();
+
+            OraclePrice {
+                price: scale(value, -SWITCHBOARD_PRECISION, expo) as i64,
+                conf: scale(std_dev, -SWITCHBOARD_PRECISION, expo)
+                    .try_into()
+                    .unwrap_or(u64::MAX),
+                expo,
+                publish_time: i64::from_le_bytes(read(data, 8 + 2208)),
+                slot: u64::from_le_bytes(read(data, 8 + 2256 + 104)),
+            }
+        }
+    }
+}
+
+/// Overwrites the price fields of any supported oracle account in place.
+pub fn set_price(account: &mut Account, price: &OraclePrice) {
+    let layout = Layout::detect(account);
+    let data = &mut account.data;
+    match layout {
+        Layout::PythLegacy => {
+            write(data, 20, &price.expo.to_le_bytes());
+            write(data, 32, &price.slot.to_le_bytes()); // last_slot
+            write(data, 40, &price.slot.to_le_bytes()); // valid_slot
+            write(data, 48, &price.price.to_le_bytes()); // ema_price.val
+            write(data, 72, &price.conf.to_le_bytes()); // ema_conf.val
+            write(data, 96, &price.publish_time.to_le_bytes());
+            write(data, 176, &price.slot.to_le_bytes()); // prev_slot
+            write(data, 184, &price.price.to_le_bytes()); // prev_price
+            write(data, 192, &price.conf.to_le_bytes()); // prev_conf
+            write(data, 200, &price.publish_time.to_le_bytes()); // prev_timestamp
+            write(data, 208, &price.price.to_le_bytes()); // agg.price
+            write(data, 216, &price.conf.to_le_bytes()); // agg.conf
+            write(data, 224, &1u32.to_le_bytes()); // agg.status = Trading
+            write(data, 232, &price.slot.to_le_bytes()); // agg.pub_slot
+        }
+        Layout::PythPull(message) => {
+            write(data, message + 32, &price.price.to_le_bytes());
+            write(data, message + 40, &price.conf.to_le_bytes());
+            write(data, message + 48, &price.expo.to_le_bytes());
+            write(data, message + 52, &price.publish_time.to_le_bytes());
+            write(data, message + 68, &price.price.to_le_bytes()); // ema_price
+            write(data, message + 76, &price.conf.to_le_bytes()); // ema_conf
+            write(data, message + 84, &price.slot.to_le_bytes()); // posted_slot
+        }
+        Layout::Switchboard => {
+            let value = scale(price.price.into(), price.expo, -SWITCHBOARD_PRECISION);
+            let std_dev = scale(price.conf.into(), price.expo, -SWITCHBOARD_PRECISION);
+
+            // submissions[0].{slot, landed_at, value}
+            write(data, 8 + 32, &price.slot.to_le_bytes());
+            write(data, 8 + 40, &price.slot.to_le_bytes());
+            write(data, 8 + 48, &value.to_le_bytes());
+            write(data, 8 + 2208, &price.publish_time.to_le_bytes()); // last_update_timestamp
+
+            // result.{value, std_dev, mean, range, min_value, max_value}
+            for (i, field) in [value, std_dev, value, 0, value, value].iter().enumerate() {
+                write(data, 8 + 2256 + i * 16, &field.to_le_bytes());
+            }
+            write(data, 8 + 2256 + 96, &[1, 0]); // num_samples, submission_idx
+            for offset in [104, 112, 120] {
+                write(data, 8 + 2256 + offset, &price.slot.to_le_bytes()); // slot, min/max_slot
+            }
+
+            write(data, 8 + 2944, &price.publish_time.to_le_bytes()); // submission_timestamps[0]
+        }
+    }
+}
+
+/// A Pyth legacy (push oracle) price account.
+pub fn pyth_legacy_account(price: &OraclePrice) -> Account {
+    let mut data = vec![0; PYTH_LEGACY_LEN];
+    write(&mut data, 0, &PYTH_MAGIC.to_le_bytes());
+    write(&mut data, 4, &2u32.to_le_bytes()); // ver
+    write(&mut data, 8, &3u32.to_le_bytes()); // atype = Price
+    write(&mut data, 12, &(PYTH_LEGACY_LEN as u32).to_le_bytes());
+    write(&mut data, 16, &1u32.to_le_bytes()); // ptype = Price
+
+    program_account(PYTH_ORACLE_PROGRAM, data, price)
+}
+
+/// A fully verified Pyth receiver `PriceUpdateV2` account for `feed_id`.
+pub fn pyth_pull_account(feed_id: [u8; 32], price: &OraclePrice) -> Account {
+    let mut data = vec![0; PYTH_PULL_LEN];
+    write(&mut data, 0, &PYTH_PULL_DISCRIMINATOR);
+    write(&mut data, 40, &[1]); // VerificationLevel::Full
+    write(&mut data, 41, &feed_id);
+
+    program_account(PYTH_RECEIVER_PROGRAM, data, price)
+}
+
+/// A Switchboard on-demand pull feed with a single submission.
+pub fn switchboard_account(price: &OraclePrice) -> Account {
+    let mut data = vec![0; SWITCHBOARD_LEN];
+    write(&mut data, 0, &SWITCHBOARD_DISCRIMINATOR);
+    write(&mut data, 8 + 2168, &1u32.to_le_bytes()); // min_responses
+    write(&mut data, 8 + 2207, &[1]); // min_sample_size
+
+    program_account(SWITCHBOARD_ON_DEMAND_PROGRAM, data, price)
+}
+
+fn program_account(owner: Pubkey, data: Vec<u8>, price: &OraclePrice) -> Account {
+    let mut account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner,
+        ..Default::default()
+    };
+    set_price(&mut account, price);
+
+    account
+}
+
+enum Layout {
+    PythLegacy,
+    /// Offset of the price message (depends on the verification level).
+    PythPull(usize),
+    Switchboard,
+}
+
+impl Layout {
+    fn detect(account: &Account) -> Self {
+        let data = &account.data;
+        match account.owner {
+            PYTH_ORACLE_PROGRAM
+                if data.len() >= 240 && u32::from_le_bytes(read(data, 0)) == PYTH_MAGIC =>
+            {
+                Layout::PythLegacy
+            }
+            PYTH_RECEIVER_PROGRAM
+                if data.len() >= PYTH_PULL_LEN && data[..8] == PYTH_PULL_DISCRIMINATOR =>
+            {
+                // VerificationLevel::Partial { num_signatures: u8 } | VerificationLevel::Full
+                match data[40] {
+                    0 => Layout::PythPull(42),
+                    _ => Layout::PythPull(41),
+                }
+            }
+            SWITCHBOARD_ON_DEMAND_PROGRAM
+                if data.len() >= SWITCHBOARD_LEN && data[..8] == SWITCHBOARD_DISCRIMINATOR =>
+            {
+                Layout::Switchboard
+            }
+            owner => panic!("Unsupported oracle account; owner={owner}; len={}", data.len()),
+        }
+    }
+}
+
+/// Rescales `value` from `10^from` to `10^to`, truncating any lost precision.
+fn scale(value: i128, from: i32, to: i32) -> i128 {
+    match from <= to {
+        true => value / 10i128.pow((to - from) as u32),
+        false => value * 10i128.pow((from - to) as u32),
+    }
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    data[offset..offset + N].try_into().unwrap()
+}
+
+fn write(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let clock = Clock { slot: 50, unix_timestamp: 1_700_000_000, ..Default::default() };
+        let price = OraclePrice::new(64_123_450_000, 1_000_000, -6, &clock);
+
+        for account in [
+            pyth_legacy_account(&price),
+            pyth_pull_account([7; 32], &price),
+            switchboard_account(&price),
+        ] {
+            let read = read_price(&account);
+            assert_eq!(scale(read.price.into(), read.expo, price.expo), price.price.into());
+            assert_eq!(scale(read.conf.into(), read.expo, price.expo), price.conf.into());
+            assert_eq!((read.publish_time, read.slot), (price.publish_time, price.slot));
+        }
+    }
+
+    #[test]
+    fn mock_price_preserves_layout() {
+        let key = Pubkey::new_unique();
+        let mut original =
+            pyth_pull_account([7; 32], &OraclePrice::new(1, 1, -8, &Clock::default()));
+        original.data[8..40].copy_from_slice(&[9; 32]);
+        let mut svm = Svm::new(crate::svm::DefaultLoader::from_iter([(key, original.clone())]));
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.slot = 1_000;
+        svm.set_sysvar(&clock);
+
+        mock_price(&mut svm, key, 2_500, 5, -2);
+
+        let account = svm.get(&key).unwrap();
+        let price = read_price(&account);
+        assert_eq!((price.price, price.conf, price.expo, price.slot), (2_500, 5, -2, 1_000));
+        assert_eq!(account.data[..73], original.data[..73]);
+    }
+}
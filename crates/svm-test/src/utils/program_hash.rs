@@ -0,0 +1,122 @@
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{bpf_loader, bpf_loader_deprecated};
+
+use crate::AccountLoader;
+
+/// SHA-256 (hex) of an ELF with trailing zeros stripped, matching the
+/// executable hash reported by `solana-verify`.
+pub fn elf_hash(elf: &[u8]) -> String {
+    let len = elf.iter().rposition(|byte| *byte != 0).map_or(0, |i| i + 1);
+
+    solana_sdk::hash::hash(&elf[..len])
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The [`elf_hash`] of an executable or `ProgramData` account.
+pub fn executable_hash(account: &Account) -> Option<String> {
+    executable_elf(account).map(elf_hash)
+}
+
+/// The ELF held by an executable or `ProgramData` account.
+pub fn executable_elf(account: &Account) -> Option<&[u8]> {
+    match account.owner {
+        bpf_loader::ID | bpf_loader_deprecated::ID if account.executable => Some(&account.data),
+        bpf_loader_upgradeable::ID => match bincode::deserialize(&account.data) {
+            Ok(UpgradeableLoaderState::ProgramData { .. }) => {
+                Some(&account.data[UpgradeableLoaderState::size_of_programdata_metadata()..])
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The [`elf_hash`] of `program_id`, resolving upgradeable programs to their
+/// `ProgramData`.
+pub fn program_hash(loader: &impl AccountLoader, program_id: &Pubkey) -> String {
+    elf_hash(&program_elf(loader, program_id))
+}
+
+/// The ELF of `program_id` (i.e. as deployed in a scenario), resolving
+/// upgradeable programs to their `ProgramData`.
+pub fn program_elf(loader: &impl AccountLoader, program_id: &Pubkey) -> Vec<u8> {
+    let program = loader.load(program_id);
+    let account = match program.owner {
+        bpf_loader_upgradeable::ID => {
+            loader.load(&bpf_loader_upgradeable::get_program_data_address(program_id))
+        }
+        _ => program,
+    };
+
+    executable_elf(&account)
+        .unwrap_or_else(|| panic!("Not a program; program_id={program_id}"))
+        .to_vec()
+}
+
+#[track_caller]
+pub fn assert_program_hash(loader: &impl AccountLoader, program_id: &Pubkey, expected: &str) {
+    let hash = program_hash(loader, program_id);
+    assert_eq!(hash, expected, "Program hash mismatch; program_id={program_id}");
+}
+
+/// Asserts the local build of `program_name` (see [`super::load_program_elf`])
+/// matches the deployed `program_id`, similar to a verifiable build check.
+#[track_caller]
+pub fn assert_local_build(loader: &impl AccountLoader, program_id: &Pubkey, program_name: &str) {
+    let local = elf_hash(&super::load_program_elf(program_name));
+    let deployed = program_hash(loader, program_id);
+    assert_eq!(
+        local, deployed,
+        "Local build does not match deployed program; program_id={program_id}; \
+         program_name={program_name}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn upgradeable_program_hash() {
+        let program_id = Pubkey::new_unique();
+        let program_data = bpf_loader_upgradeable::get_program_data_address(&program_id);
+        let elf = [0x7f, b'E', b'L', b'F', 1, 2, 3];
+
+        let mut data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: None,
+        })
+        .unwrap();
+        data.resize(UpgradeableLoaderState::size_of_programdata_metadata(), 0);
+        data.extend(elf);
+        data.extend([0; 64]);
+
+        let loader = BTreeMap::from_iter([
+            (
+                program_id,
+                Account {
+                    data: bincode::serialize(&UpgradeableLoaderState::Program {
+                        programdata_address: program_data,
+                    })
+                    .unwrap(),
+                    owner: bpf_loader_upgradeable::ID,
+                    executable: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                program_data,
+                Account { data, owner: bpf_loader_upgradeable::ID, ..Default::default() },
+            ),
+        ]);
+
+        assert_program_hash(&loader, &program_id, &elf_hash(&elf));
+    }
+}
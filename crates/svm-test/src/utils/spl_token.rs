@@ -5,7 +5,7 @@ use solana_sdk::pubkey;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::rent::Rent;
 use spl_associated_token_account::get_associated_token_address;
-use spl_token::state::AccountState;
+use spl_token::state::{AccountState, Mint};
 
 use crate::{AccountLoader, Svm};
 
@@ -16,12 +16,130 @@ where
     L: AccountLoader,
 {
     let key = get_associated_token_address(&owner, &mint);
-    let account = token(mint, owner, amount);
-    svm.set(key, program_account(account));
+
+    mock_token_account(svm, key, &TokenAccountBuilder::new(mint, owner, amount))
+}
+
+pub fn mock_token_account<L>(svm: &mut Svm<L>, key: Pubkey, account: &TokenAccountBuilder) -> Pubkey
+where
+    L: AccountLoader,
+{
+    svm.set(key, account.build());
+
+    key
+}
+
+pub fn mock_mint<L>(svm: &mut Svm<L>, key: Pubkey, mint: &MintBuilder) -> Pubkey
+where
+    L: AccountLoader,
+{
+    svm.set(key, mint.build());
 
     key
 }
 
+/// Loads a mint (i.e. from a scenario), applies `modify` & writes it back
+/// leaving all other fields untouched.
+pub fn modify_mint<L>(svm: &mut Svm<L>, key: Pubkey, modify: impl FnOnce(&mut Mint))
+where
+    L: AccountLoader,
+{
+    let mut account = svm.get_or_load(&key);
+    assert_eq!(account.owner, spl_token::ID, "Not a token program mint; key={key}");
+    let mut mint = Mint::unpack(&account.data)
+        .unwrap_or_else(|err| panic!("Failed to unpack mint; key={key}; err={err}"));
+    modify(&mut mint);
+    Mint::pack(mint, &mut account.data).unwrap();
+
+    svm.set(key, account);
+}
+
+#[derive(Debug, Clone)]
+pub struct MintBuilder {
+    mint: Mint,
+}
+
+impl MintBuilder {
+    pub fn new(decimals: u8) -> Self {
+        MintBuilder {
+            mint: Mint {
+                mint_authority: COption::None,
+                supply: 0,
+                decimals,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+        }
+    }
+
+    pub fn supply(mut self, supply: u64) -> Self {
+        self.mint.supply = supply;
+
+        self
+    }
+
+    pub fn mint_authority(mut self, authority: Option<Pubkey>) -> Self {
+        self.mint.mint_authority = authority.into();
+
+        self
+    }
+
+    pub fn freeze_authority(mut self, authority: Option<Pubkey>) -> Self {
+        self.mint.freeze_authority = authority.into();
+
+        self
+    }
+
+    pub fn build(&self) -> Account {
+        Account {
+            lamports: Rent::default().minimum_balance(Mint::LEN),
+            data: super::pack_to_vec(self.mint),
+            owner: spl_token::ID,
+            ..Default::default()
+        }
+    }
+}
+
+/// Builds token accounts, native (WSOL) accounts get the correct `is_native` &
+/// lamports.
+#[derive(Debug, Clone)]
+pub struct TokenAccountBuilder {
+    account: spl_token::state::Account,
+}
+
+impl TokenAccountBuilder {
+    pub fn new(mint: Pubkey, owner: Pubkey, amount: u64) -> Self {
+        TokenAccountBuilder { account: token(mint, owner, amount) }
+    }
+
+    pub fn delegate(mut self, delegate: Pubkey, delegated_amount: u64) -> Self {
+        self.account.delegate = COption::Some(delegate);
+        self.account.delegated_amount = delegated_amount;
+
+        self
+    }
+
+    pub fn frozen(mut self) -> Self {
+        self.account.state = AccountState::Frozen;
+
+        self
+    }
+
+    pub fn close_authority(mut self, authority: Pubkey) -> Self {
+        self.account.close_authority = COption::Some(authority);
+
+        self
+    }
+
+    pub fn state(&self) -> spl_token::state::Account {
+        self.account
+    }
+
+    pub fn build(&self) -> Account {
+        program_account(self.account)
+    }
+}
+
 pub fn program_account(account: spl_token::state::Account) -> Account {
     let rent = Rent::default().minimum_balance(spl_token::state::Account::LEN);
     let lamports = match account.mint {
@@ -53,3 +171,39 @@ pub fn token(mint: Pubkey, owner: Pubkey, amount: u64) -> spl_token::state::Acco
         close_authority: COption::None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svm::DefaultLoader;
+
+    #[test]
+    fn modify_mint() {
+        let key = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut svm = Svm::new(DefaultLoader::from_iter([(
+            key,
+            MintBuilder::new(6)
+                .supply(100)
+                .freeze_authority(Some(authority))
+                .build(),
+        )]));
+
+        super::modify_mint(&mut svm, key, |mint| mint.supply = 200);
+
+        let mint = Mint::unpack(&svm.get(&key).unwrap().data).unwrap();
+        assert_eq!(mint.supply, 200);
+        assert_eq!(mint.decimals, 6);
+        assert_eq!(mint.freeze_authority, COption::Some(authority));
+    }
+
+    #[test]
+    fn native_token_account() {
+        let account = TokenAccountBuilder::new(WSOL, Pubkey::new_unique(), 10u64.pow(9)).build();
+        let rent = Rent::default().minimum_balance(spl_token::state::Account::LEN);
+
+        let state = spl_token::state::Account::unpack(&account.data).unwrap();
+        assert_eq!(state.is_native, COption::Some(rent));
+        assert_eq!(account.lamports, 10u64.pow(9) + rent);
+    }
+}
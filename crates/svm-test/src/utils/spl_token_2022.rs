@@ -0,0 +1,481 @@
+use solana_sdk::account::Account;
+use solana_sdk::program_option::COption;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_pod::optional_keys::OptionalNonZeroPubkey;
+use spl_token_2022::extension::default_account_state::DefaultAccountState;
+use spl_token_2022::extension::immutable_owner::ImmutableOwner;
+use spl_token_2022::extension::interest_bearing_mint::InterestBearingConfig;
+use spl_token_2022::extension::memo_transfer::MemoTransfer;
+use spl_token_2022::extension::metadata_pointer::MetadataPointer;
+use spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+use spl_token_2022::extension::transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig};
+use spl_token_2022::extension::transfer_hook::{TransferHook, TransferHookAccount};
+use spl_token_2022::extension::{
+    BaseState, BaseStateWithExtensions, BaseStateWithExtensionsMut, ExtensionType,
+    StateWithExtensions, StateWithExtensionsMut,
+};
+use spl_token_2022::state::{Account as TokenAccount, AccountState, Mint};
+use spl_token_metadata_interface::state::TokenMetadata;
+
+use crate::{AccountLoader, Svm};
+
+pub fn mock_mint<L>(svm: &mut Svm<L>, key: Pubkey, mint: &MintBuilder) -> Pubkey
+where
+    L: AccountLoader,
+{
+    svm.set(key, mint.build(&key));
+
+    key
+}
+
+/// Mocks a Token-2022 ATA, the ATA is derived using the Token-2022 program ID
+/// & always has the `ImmutableOwner` extension (as it would on chain).
+pub fn mock_ata<L>(svm: &mut Svm<L>, account: TokenAccountBuilder) -> Pubkey
+where
+    L: AccountLoader,
+{
+    let key = get_associated_token_address_with_program_id(
+        &account.account.owner,
+        &account.account.mint,
+        &spl_token_2022::ID,
+    );
+    svm.set(key, account.immutable_owner().build());
+
+    key
+}
+
+#[derive(Debug, Clone)]
+pub struct MintBuilder {
+    mint: Mint,
+    transfer_fee: Option<TransferFeeConfig>,
+    interest_bearing: Option<InterestBearingConfig>,
+    default_account_state: Option<AccountState>,
+    permanent_delegate: Option<Pubkey>,
+    transfer_hook: Option<Pubkey>,
+    metadata_pointer: Option<Pubkey>,
+    token_metadata: Option<(String, String, String)>,
+}
+
+impl MintBuilder {
+    pub fn new(decimals: u8) -> Self {
+        MintBuilder {
+            mint: Mint {
+                mint_authority: COption::None,
+                supply: 0,
+                decimals,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+            transfer_fee: None,
+            interest_bearing: None,
+            default_account_state: None,
+            permanent_delegate: None,
+            transfer_hook: None,
+            metadata_pointer: None,
+            token_metadata: None,
+        }
+    }
+
+    pub fn supply(mut self, supply: u64) -> Self {
+        self.mint.supply = supply;
+
+        self
+    }
+
+    pub fn mint_authority(mut self, authority: Option<Pubkey>) -> Self {
+        self.mint.mint_authority = authority.into();
+
+        self
+    }
+
+    pub fn freeze_authority(mut self, authority: Option<Pubkey>) -> Self {
+        self.mint.freeze_authority = authority.into();
+
+        self
+    }
+
+    /// Sets the same fee for both the older & newer transfer fee.
+    pub fn transfer_fee(mut self, basis_points: u16, maximum_fee: u64) -> Self {
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        };
+        self.transfer_fee = Some(TransferFeeConfig {
+            transfer_fee_config_authority: OptionalNonZeroPubkey::default(),
+            withdraw_withheld_authority: OptionalNonZeroPubkey::default(),
+            withheld_amount: 0.into(),
+            older_transfer_fee: fee,
+            newer_transfer_fee: fee,
+        });
+
+        self
+    }
+
+    /// Sets a constant interest rate since `initialization_timestamp`.
+    pub fn interest_bearing(mut self, rate_bps: i16, initialization_timestamp: i64) -> Self {
+        self.interest_bearing = Some(InterestBearingConfig {
+            rate_authority: OptionalNonZeroPubkey::default(),
+            initialization_timestamp: initialization_timestamp.into(),
+            pre_update_average_rate: rate_bps.into(),
+            last_update_timestamp: initialization_timestamp.into(),
+            current_rate: rate_bps.into(),
+        });
+
+        self
+    }
+
+    pub fn default_account_state(mut self, state: AccountState) -> Self {
+        self.default_account_state = Some(state);
+
+        self
+    }
+
+    pub fn permanent_delegate(mut self, delegate: Pubkey) -> Self {
+        self.permanent_delegate = Some(delegate);
+
+        self
+    }
+
+    pub fn transfer_hook(mut self, program_id: Pubkey) -> Self {
+        self.transfer_hook = Some(program_id);
+
+        self
+    }
+
+    pub fn metadata_pointer(mut self, metadata_address: Pubkey) -> Self {
+        self.metadata_pointer = Some(metadata_address);
+
+        self
+    }
+
+    /// Stores token metadata on the mint itself, if no metadata pointer has
+    /// been set it will point to the mint.
+    pub fn token_metadata(
+        mut self,
+        name: impl Into<String>,
+        symbol: impl Into<String>,
+        uri: impl Into<String>,
+    ) -> Self {
+        self.token_metadata = Some((name.into(), symbol.into(), uri.into()));
+
+        self
+    }
+
+    pub fn extension_types(&self) -> Vec<ExtensionType> {
+        [
+            self.transfer_fee.map(|_| ExtensionType::TransferFeeConfig),
+            self.interest_bearing
+                .map(|_| ExtensionType::InterestBearingConfig),
+            self.default_account_state
+                .map(|_| ExtensionType::DefaultAccountState),
+            self.permanent_delegate
+                .map(|_| ExtensionType::PermanentDelegate),
+            self.transfer_hook.map(|_| ExtensionType::TransferHook),
+            (self.metadata_pointer.is_some() || self.token_metadata.is_some())
+                .then_some(ExtensionType::MetadataPointer),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    pub fn build(&self, key: &Pubkey) -> Account {
+        let mut data = pack_with_extensions(self.mint, &self.extension_types(), |state| {
+            if let Some(config) = self.transfer_fee {
+                *state.init_extension::<TransferFeeConfig>(true).unwrap() = config;
+            }
+            if let Some(config) = self.interest_bearing {
+                *state.init_extension::<InterestBearingConfig>(true).unwrap() = config;
+            }
+            if let Some(default) = self.default_account_state {
+                state
+                    .init_extension::<DefaultAccountState>(true)
+                    .unwrap()
+                    .state = default as u8;
+            }
+            if let Some(delegate) = self.permanent_delegate {
+                state
+                    .init_extension::<PermanentDelegate>(true)
+                    .unwrap()
+                    .delegate = non_zero(delegate);
+            }
+            if let Some(program_id) = self.transfer_hook {
+                state
+                    .init_extension::<TransferHook>(true)
+                    .unwrap()
+                    .program_id = non_zero(program_id);
+            }
+            if self.metadata_pointer.is_some() || self.token_metadata.is_some() {
+                state
+                    .init_extension::<MetadataPointer>(true)
+                    .unwrap()
+                    .metadata_address = non_zero(self.metadata_pointer.unwrap_or(*key));
+            }
+        });
+
+        // Token metadata is variable length so must be allocated after the fixed
+        // extensions.
+        if let Some((name, symbol, uri)) = &self.token_metadata {
+            let metadata = TokenMetadata {
+                update_authority: OptionalNonZeroPubkey::default(),
+                mint: *key,
+                name: name.clone(),
+                symbol: symbol.clone(),
+                uri: uri.clone(),
+                additional_metadata: Vec::default(),
+            };
+            let len = StateWithExtensions::<Mint>::unpack(&data)
+                .unwrap()
+                .try_get_new_account_len_for_variable_len_extension(&metadata)
+                .unwrap();
+            data.resize(len, 0);
+            StateWithExtensionsMut::<Mint>::unpack(&mut data)
+                .unwrap()
+                .init_variable_len_extension(&metadata, false)
+                .unwrap();
+        }
+
+        program_account(data)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenAccountBuilder {
+    account: TokenAccount,
+    extensions: Vec<ExtensionType>,
+    withheld_amount: u64,
+}
+
+impl TokenAccountBuilder {
+    /// Accounts of the native mint hold `amount` plus the rent reserve of the
+    /// final account length, see [`Self::build`].
+    pub fn new(mint: Pubkey, owner: Pubkey, amount: u64) -> Self {
+        TokenAccountBuilder {
+            account: TokenAccount {
+                mint,
+                owner,
+                amount,
+                delegate: COption::None,
+                delegated_amount: 0,
+                state: AccountState::Initialized,
+                is_native: COption::None,
+                close_authority: COption::None,
+            },
+            extensions: Vec::default(),
+            withheld_amount: 0,
+        }
+    }
+
+    /// Adds the account extensions required by `mint` (i.e. transfer fee
+    /// amount & transfer hook account).
+    pub fn for_mint(mut self, mint: &MintBuilder) -> Self {
+        self.extensions
+            .extend(ExtensionType::get_required_init_account_extensions(&mint.extension_types()));
+        if let Some(state) = mint.default_account_state {
+            self.account.state = state;
+        }
+
+        self
+    }
+
+    pub fn delegate(mut self, delegate: Pubkey, delegated_amount: u64) -> Self {
+        self.account.delegate = COption::Some(delegate);
+        self.account.delegated_amount = delegated_amount;
+
+        self
+    }
+
+    pub fn state(mut self, state: AccountState) -> Self {
+        self.account.state = state;
+
+        self
+    }
+
+    pub fn close_authority(mut self, authority: Pubkey) -> Self {
+        self.account.close_authority = COption::Some(authority);
+
+        self
+    }
+
+    pub fn withheld_amount(mut self, withheld_amount: u64) -> Self {
+        self.extensions.push(ExtensionType::TransferFeeAmount);
+        self.withheld_amount = withheld_amount;
+
+        self
+    }
+
+    pub fn immutable_owner(mut self) -> Self {
+        self.extensions.push(ExtensionType::ImmutableOwner);
+
+        self
+    }
+
+    pub fn memo_required(mut self) -> Self {
+        self.extensions.push(ExtensionType::MemoTransfer);
+
+        self
+    }
+
+    pub fn build(&self) -> Account {
+        let extensions = self
+            .extensions
+            .iter()
+            .fold(Vec::default(), |mut unique, extension| {
+                if !unique.contains(extension) {
+                    unique.push(*extension);
+                }
+
+                unique
+            });
+
+        // The native rent reserve depends on the length including extensions.
+        let mut base = self.account;
+        if base.mint == spl_token_2022::native_mint::ID {
+            let len =
+                ExtensionType::try_calculate_account_len::<TokenAccount>(&extensions).unwrap();
+            base.is_native = COption::Some(Rent::default().minimum_balance(len));
+        }
+
+        let data = pack_with_extensions(base, &extensions, |state| {
+            for extension in &extensions {
+                match extension {
+                    ExtensionType::TransferFeeAmount => {
+                        state
+                            .init_extension::<TransferFeeAmount>(true)
+                            .unwrap()
+                            .withheld_amount = self.withheld_amount.into();
+                    }
+                    ExtensionType::ImmutableOwner => {
+                        state.init_extension::<ImmutableOwner>(true).unwrap();
+                    }
+                    ExtensionType::MemoTransfer => {
+                        state
+                            .init_extension::<MemoTransfer>(true)
+                            .unwrap()
+                            .require_incoming_transfer_memos = true.into();
+                    }
+                    ExtensionType::TransferHookAccount => {
+                        state.init_extension::<TransferHookAccount>(true).unwrap();
+                    }
+                    extension => panic!("Unsupported account extension; extension={extension:?}"),
+                }
+            }
+        });
+
+        let mut account = program_account(data);
+        if let COption::Some(rent) = base.is_native {
+            account.lamports = base.amount + rent;
+        }
+
+        account
+    }
+}
+
+pub fn program_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token_2022::ID,
+        ..Default::default()
+    }
+}
+
+fn pack_with_extensions<S>(
+    base: S,
+    extensions: &[ExtensionType],
+    init: impl FnOnce(&mut StateWithExtensionsMut<S>),
+) -> Vec<u8>
+where
+    S: BaseState + Pack,
+{
+    let mut data = vec![0; ExtensionType::try_calculate_account_len::<S>(extensions).unwrap()];
+    let mut state = StateWithExtensionsMut::<S>::unpack_uninitialized(&mut data).unwrap();
+    init(&mut state);
+    state.base = base;
+    state.pack_base();
+    state.init_account_type().unwrap();
+
+    data
+}
+
+fn non_zero(key: Pubkey) -> OptionalNonZeroPubkey {
+    OptionalNonZeroPubkey::try_from(Some(key)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mint_with_extensions() {
+        let key = Pubkey::new_unique();
+        let account = MintBuilder::new(6)
+            .supply(100)
+            .transfer_fee(50, 5_000)
+            .permanent_delegate(Pubkey::new_unique())
+            .token_metadata("Test", "TST", "https://example.com")
+            .build(&key);
+        assert_eq!(account.owner, spl_token_2022::ID);
+        assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
+
+        let state = StateWithExtensions::<Mint>::unpack(&account.data).unwrap();
+        assert_eq!(state.base.supply, 100);
+        assert_eq!(state.try_get_account_len().unwrap(), account.data.len());
+        assert_eq!(
+            state
+                .get_variable_len_extension::<TokenMetadata>()
+                .unwrap()
+                .symbol,
+            "TST"
+        );
+        assert_eq!(
+            Option::<Pubkey>::from(
+                state
+                    .get_extension::<MetadataPointer>()
+                    .unwrap()
+                    .metadata_address
+            ),
+            Some(key)
+        );
+    }
+
+    #[test]
+    fn account_for_mint() {
+        let mint = MintBuilder::new(6)
+            .transfer_fee(50, 5_000)
+            .transfer_hook(Pubkey::new_unique());
+        let account = TokenAccountBuilder::new(Pubkey::new_unique(), Pubkey::new_unique(), 10)
+            .for_mint(&mint)
+            .immutable_owner()
+            .build();
+
+        let state = StateWithExtensions::<TokenAccount>::unpack(&account.data).unwrap();
+        assert_eq!(state.base.amount, 10);
+        assert_eq!(
+            state.get_extension_types().unwrap(),
+            vec![
+                ExtensionType::TransferFeeAmount,
+                ExtensionType::TransferHookAccount,
+                ExtensionType::ImmutableOwner,
+            ]
+        );
+    }
+
+    #[test]
+    fn native_ata_reserve() {
+        let account =
+            TokenAccountBuilder::new(spl_token_2022::native_mint::ID, Pubkey::new_unique(), 10)
+                .immutable_owner()
+                .build();
+        let rent = Rent::default().minimum_balance(account.data.len());
+
+        let state = StateWithExtensions::<TokenAccount>::unpack(&account.data).unwrap();
+        assert!(account.data.len() > TokenAccount::LEN);
+        assert_eq!(state.base.is_native, COption::Some(rent));
+        assert_eq!(account.lamports, rent + 10);
+    }
+}
@@ -0,0 +1,274 @@
+use solana_sdk::account::Account;
+use solana_sdk::clock::{Clock, Epoch};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::stake::stake_flags::StakeFlags;
+use solana_sdk::stake::state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2};
+use solana_sdk::stake_history::{StakeHistory, StakeHistoryEntry};
+use solana_sdk::vote::state::{VoteInit, VoteState, VoteStateVersions};
+
+use crate::{AccountLoader, Svm};
+
+pub fn mock_vote_account<L>(svm: &mut Svm<L>, key: Pubkey, vote: &VoteAccountBuilder) -> Pubkey
+where
+    L: AccountLoader,
+{
+    let account = vote.build(&svm.get_sysvar(), &svm.get_sysvar());
+    svm.set(key, account);
+
+    key
+}
+
+pub fn mock_stake_account<L>(svm: &mut Svm<L>, key: Pubkey, stake: &StakeAccountBuilder) -> Pubkey
+where
+    L: AccountLoader,
+{
+    let account = stake.build(&svm.get_sysvar());
+    svm.set(key, account);
+
+    key
+}
+
+/// Warps to the first slot of `epoch`, recording `cluster` in the stake
+/// history for every epoch passed.
+///
+/// `cluster` is the cluster wide stake (i.e. the sum of all delegations), it
+/// determines how quickly individual stake accounts warm up & cool down.
+pub fn warp_to_epoch_with_stake_history<L>(
+    svm: &mut Svm<L>,
+    epoch: Epoch,
+    cluster: StakeHistoryEntry,
+) where
+    L: AccountLoader,
+{
+    let current = svm.get_sysvar::<Clock>().epoch;
+    assert!(epoch > current, "Cannot warp backwards; current={current}; epoch={epoch}");
+
+    let mut history = svm.get_sysvar::<StakeHistory>();
+    for passed in current..epoch {
+        history.add(passed, cluster.clone());
+    }
+    svm.set_sysvar(&history);
+    svm.warp_to_epoch(epoch);
+}
+
+/// Advances `epochs` epochs, see [`warp_to_epoch_with_stake_history`].
+pub fn advance_epochs<L>(svm: &mut Svm<L>, epochs: u64, cluster: StakeHistoryEntry)
+where
+    L: AccountLoader,
+{
+    let current = svm.get_sysvar::<Clock>().epoch;
+
+    warp_to_epoch_with_stake_history(svm, current + epochs, cluster);
+}
+
+#[derive(Debug, Clone)]
+pub struct VoteAccountBuilder {
+    init: VoteInit,
+    credits: Vec<(Epoch, u64)>,
+}
+
+impl VoteAccountBuilder {
+    pub fn new(node: Pubkey, authorized_voter: Pubkey, authorized_withdrawer: Pubkey) -> Self {
+        VoteAccountBuilder {
+            init: VoteInit {
+                node_pubkey: node,
+                authorized_voter,
+                authorized_withdrawer,
+                commission: 0,
+            },
+            credits: Vec::default(),
+        }
+    }
+
+    pub fn commission(mut self, commission: u8) -> Self {
+        self.init.commission = commission;
+
+        self
+    }
+
+    /// Credits earned in `epoch`, must be called in ascending epoch order.
+    pub fn credits(mut self, epoch: Epoch, credits: u64) -> Self {
+        self.credits.push((epoch, credits));
+
+        self
+    }
+
+    pub fn build(&self, clock: &Clock, rent: &Rent) -> Account {
+        let mut state = VoteState::new(&self.init, clock);
+        for (epoch, credits) in &self.credits {
+            state.increment_credits(*epoch, *credits);
+        }
+
+        let mut data = vec![0; VoteState::size_of()];
+        VoteState::serialize(&VoteStateVersions::new_current(state), &mut data).unwrap();
+
+        Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: solana_sdk::vote::program::ID,
+            ..Default::default()
+        }
+    }
+}
+
+/// Builds initialized or delegated stake accounts.
+///
+/// `lamports` excludes the rent exempt reserve, for delegated accounts it is
+/// the delegated stake.
+#[derive(Debug, Clone)]
+pub struct StakeAccountBuilder {
+    authorized: Authorized,
+    lockup: Lockup,
+    lamports: u64,
+    delegation: Option<(Delegation, u64)>,
+}
+
+impl StakeAccountBuilder {
+    pub fn new(staker: Pubkey, withdrawer: Pubkey, lamports: u64) -> Self {
+        StakeAccountBuilder {
+            authorized: Authorized { staker, withdrawer },
+            lockup: Lockup::default(),
+            lamports,
+            delegation: None,
+        }
+    }
+
+    pub fn lockup(mut self, lockup: Lockup) -> Self {
+        self.lockup = lockup;
+
+        self
+    }
+
+    pub fn delegate(mut self, vote: Pubkey, activation_epoch: Epoch) -> Self {
+        self.delegation = Some((Delegation::new(&vote, self.lamports, activation_epoch), 0));
+
+        self
+    }
+
+    pub fn deactivate(mut self, deactivation_epoch: Epoch) -> Self {
+        let (delegation, _) = self
+            .delegation
+            .as_mut()
+            .expect("Stake must be delegated first");
+        delegation.deactivation_epoch = deactivation_epoch;
+
+        self
+    }
+
+    pub fn credits_observed(mut self, credits_observed: u64) -> Self {
+        let (_, credits) = self
+            .delegation
+            .as_mut()
+            .expect("Stake must be delegated first");
+        *credits = credits_observed;
+
+        self
+    }
+
+    pub fn build(&self, rent: &Rent) -> Account {
+        let rent_exempt_reserve = rent.minimum_balance(StakeStateV2::size_of());
+        let meta = Meta { rent_exempt_reserve, authorized: self.authorized, lockup: self.lockup };
+        let state = match self.delegation {
+            Some((delegation, credits_observed)) => StakeStateV2::Stake(
+                meta,
+                Stake { delegation, credits_observed },
+                StakeFlags::empty(),
+            ),
+            None => StakeStateV2::Initialized(meta),
+        };
+
+        let mut data = vec![0; StakeStateV2::size_of()];
+        bincode::serialize_into(&mut data[..], &state).unwrap();
+
+        Account {
+            lamports: rent_exempt_reserve + self.lamports,
+            data,
+            owner: solana_sdk::stake::program::ID,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::stake::state::StakeActivationStatus;
+
+    use super::*;
+    use crate::svm::DefaultLoader;
+
+    fn status<L>(svm: &Svm<L>, stake: &Pubkey) -> StakeActivationStatus
+    where
+        L: AccountLoader,
+    {
+        let StakeStateV2::Stake(_, state, _) =
+            bincode::deserialize(&svm.get(stake).unwrap().data).unwrap()
+        else {
+            panic!("Expected delegated stake");
+        };
+
+        state.delegation.stake_activating_and_deactivating(
+            svm.get_sysvar::<Clock>().epoch,
+            &svm.get_sysvar::<StakeHistory>(),
+            Some(0),
+        )
+    }
+
+    #[test]
+    fn stake_warms_up_and_cools_down() {
+        let mut svm: Svm<DefaultLoader> = Svm::default();
+        let vote = Pubkey::new_unique();
+        let stake = Pubkey::new_unique();
+        let deactivated = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        mock_vote_account(
+            &mut svm,
+            vote,
+            &VoteAccountBuilder::new(authority, authority, authority),
+        );
+        mock_stake_account(
+            &mut svm,
+            stake,
+            &StakeAccountBuilder::new(authority, authority, 10u64.pow(12)).delegate(vote, 0),
+        );
+        mock_stake_account(
+            &mut svm,
+            deactivated,
+            &StakeAccountBuilder::new(authority, authority, 10u64.pow(12))
+                .delegate(vote, 0)
+                .deactivate(2),
+        );
+
+        // Activating epoch, nothing is effective yet.
+        assert_eq!(
+            status(&svm, &stake),
+            StakeActivationStatus::with_effective_and_activating(0, 10u64.pow(12))
+        );
+
+        // Cluster has 1M SOL active & our 1k SOL activating.
+        let cluster = StakeHistoryEntry {
+            effective: 10u64.pow(15),
+            activating: 10u64.pow(12),
+            deactivating: 0,
+        };
+        warp_to_epoch_with_stake_history(&mut svm, 2, cluster);
+        assert_eq!(svm.get_sysvar::<Clock>().epoch, 2);
+        assert_eq!(status(&svm, &stake), StakeActivationStatus::with_effective(10u64.pow(12)));
+
+        // Deactivating epoch, still effective until the epoch ends.
+        assert_eq!(
+            status(&svm, &deactivated),
+            StakeActivationStatus::with_deactivating(10u64.pow(12))
+        );
+
+        // Cluster has 1M SOL active & our 1k SOL deactivating.
+        let cluster = StakeHistoryEntry {
+            effective: 10u64.pow(15),
+            activating: 0,
+            deactivating: 10u64.pow(12),
+        };
+        advance_epochs(&mut svm, 1, cluster);
+        assert_eq!(status(&svm, &stake), StakeActivationStatus::with_effective(10u64.pow(12)));
+        assert_eq!(status(&svm, &deactivated), StakeActivationStatus::default());
+    }
+}
@@ -0,0 +1,297 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::read_keypair_file;
+use solana_sdk::signer::Signer;
+
+static METADATA: OnceLock<Metadata> = OnceLock::new();
+
+#[derive(Debug, Default, Deserialize)]
+struct Metadata {
+    packages: Vec<WorkspacePackage>,
+    target_directory: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkspacePackage {
+    pub name: String,
+    pub manifest_path: PathBuf,
+    pub targets: Vec<Target>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Target {
+    pub name: String,
+    pub crate_types: Vec<String>,
+}
+
+impl WorkspacePackage {
+    /// The name of the `.so` produced by `cargo build-sbf`, if this package
+    /// builds a `cdylib`.
+    pub fn program_name(&self) -> Option<String> {
+        self.targets
+            .iter()
+            .find(|target| target.crate_types.iter().any(|kind| kind == "cdylib"))
+            .map(|target| target.name.replace('-', "_"))
+    }
+}
+
+/// The packages of the current cargo workspace, empty if `cargo metadata`
+/// fails.
+pub fn workspace_packages() -> &'static [WorkspacePackage] {
+    &metadata().packages
+}
+
+/// The program name & id of every SBF program in the workspace, see
+/// [`workspace_program_id`].
+pub fn workspace_programs() -> Vec<(String, Pubkey)> {
+    workspace_packages()
+        .iter()
+        .filter_map(WorkspacePackage::program_name)
+        .map(|name| {
+            let program_id = workspace_program_id(&name);
+
+            (name, program_id)
+        })
+        .collect()
+}
+
+/// The program id of a workspace program (by package or program name).
+///
+/// The id is read from `target/deploy/<name>-keypair.json` & checked against
+/// any `declare_id!` in the program's `src/lib.rs`.
+pub fn workspace_program_id(name: &str) -> Pubkey {
+    program_id(metadata(), name)
+}
+
+fn program_id(metadata: &Metadata, name: &str) -> Pubkey {
+    let name = name.replace('-', "_");
+    let package = metadata
+        .packages
+        .iter()
+        .find(|package| {
+            package.name.replace('-', "_") == name || package.program_name().as_ref() == Some(&name)
+        })
+        .unwrap_or_else(|| panic!("Program not found in workspace; name={name}"));
+    let program_name = package
+        .program_name()
+        .unwrap_or_else(|| panic!("Package is not a program; name={name}"));
+
+    let keypair_path = metadata
+        .target_directory
+        .join(format!("deploy/{program_name}-keypair.json"));
+    let keypair = keypair_path
+        .exists()
+        .then(|| {
+            read_keypair_file(&keypair_path).unwrap_or_else(|err| {
+                panic!("Failed to read program keypair; path={keypair_path:?}; err={err}")
+            })
+        })
+        .map(|keypair| keypair.pubkey());
+    let declared = declared_id(&package.manifest_path.parent().unwrap().join("src/lib.rs"));
+
+    match (keypair, declared) {
+        (Some(keypair), Some(declared)) => {
+            assert_eq!(
+                keypair, declared,
+                "Deploy keypair does not match declare_id!; program={program_name}; \
+                 path={keypair_path:?}"
+            );
+
+            keypair
+        }
+        (Some(program_id), None) | (None, Some(program_id)) => program_id,
+        (None, None) => panic!(
+            "Missing deploy keypair & declare_id!, build with `cargo build-sbf`; \
+             program={program_name}; path={keypair_path:?}"
+        ),
+    }
+}
+
+fn metadata() -> &'static Metadata {
+    METADATA.get_or_init(|| {
+        let cargo = std::env::var("CARGO").unwrap_or("cargo".to_owned());
+        let Some(output) = Command::new(cargo)
+            .args(["metadata", "--format-version", "1", "--no-deps"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+        else {
+            return Metadata::default();
+        };
+
+        serde_json::from_slice(&output.stdout)
+            .unwrap_or_else(|err| panic!("Failed to parse cargo metadata; err={err}"))
+    })
+}
+
+/// The `declare_id!("...")` in `lib`, skipping commented out lines.
+fn declared_id(lib: &Path) -> Option<Pubkey> {
+    let source = std::fs::read_to_string(lib).ok()?;
+
+    source
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| !line.starts_with("//"))
+        .find_map(|line| {
+            let start = line.find("declare_id!(\"")? + "declare_id!(\"".len();
+            let end = start + line[start..].find('"')?;
+
+            Some(line[start..end].parse().unwrap_or_else(|err| {
+                panic!("Failed to parse declare_id!; path={lib:?}; err={err}")
+            }))
+        })
+}
+
+/// Checks whether `elf` is older than the `src` directory or manifest of its
+/// workspace package, returning the path of the rebuilt ELF if the program was
+/// rebuilt.
+///
+/// Only ELFs resolved from the workspace's `target/deploy` are checked, pinned
+/// ELFs (i.e. in `tests/fixtures`) sharing a program's name are left alone.
+///
+/// Behavior is controlled via `TEST_STALE_SBF`: panic (default), `warn`,
+/// `rebuild` (via `cargo build-sbf`) or `ignore`.
+pub(crate) fn check_stale_elf(program_name: &str, elf: &Path) -> Option<PathBuf> {
+    let mode = std::env::var("TEST_STALE_SBF").unwrap_or_default();
+    if mode == "ignore" {
+        return None;
+    }
+
+    let deploy_dir = metadata().target_directory.join("deploy");
+    let in_deploy_dir = elf
+        .parent()
+        .and_then(|dir| dir.canonicalize().ok())
+        .is_some_and(|dir| deploy_dir.canonicalize().is_ok_and(|deploy| deploy == dir));
+    if !in_deploy_dir {
+        return None;
+    }
+    let package = workspace_packages()
+        .iter()
+        .find(|package| package.program_name().as_deref() == Some(program_name))?;
+    let built = modified(elf)?;
+    let manifest = &package.manifest_path;
+    let (source, _) = newest_file(&manifest.parent().unwrap().join("src"))
+        .into_iter()
+        .chain(modified(manifest).map(|modified| (manifest.clone(), modified)))
+        .max_by_key(|(_, modified)| *modified)
+        .filter(|(_, modified)| *modified > built)?;
+
+    let message = format!(
+        "Stale SBF build, rebuild with `cargo build-sbf --manifest-path {}`; \
+         program={program_name}; elf={elf:?}; source={source:?}",
+        manifest.display()
+    );
+    match mode.as_str() {
+        "warn" => {
+            eprintln!("{message}");
+
+            None
+        }
+        "rebuild" => {
+            let status = Command::new(std::env::var("CARGO").unwrap_or("cargo".to_owned()))
+                .arg("build-sbf")
+                .arg("--manifest-path")
+                .arg(manifest)
+                .arg("--sbf-out-dir")
+                .arg(&deploy_dir)
+                .status()
+                .unwrap_or_else(|err| panic!("Failed to run cargo build-sbf; err={err}"));
+            assert!(status.success(), "{message}; status={status}");
+
+            Some(deploy_dir.join(format!("{program_name}.so")))
+        }
+        _ => panic!("{message}"),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// The most recently modified file under `dir`, skipping hidden entries.
+fn newest_file(dir: &Path) -> Option<(PathBuf, SystemTime)> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| {
+            let path = entry.path();
+            match path.is_dir() {
+                true => newest_file(&path),
+                false => modified(&path).map(|modified| (path, modified)),
+            }
+        })
+        .max_by_key(|(_, modified)| *modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::{write_keypair_file, Keypair};
+
+    use super::*;
+
+    #[test]
+    fn program_id_from_keypair_and_lib() {
+        let dir = std::env::temp_dir().join(format!("svm-test-workspace-{}", std::process::id()));
+        let manifest_path = dir.join("programs/faucet/Cargo.toml");
+        std::fs::create_dir_all(dir.join("programs/faucet/src")).unwrap();
+        std::fs::create_dir_all(dir.join("target/deploy")).unwrap();
+        let metadata = Metadata {
+            packages: vec![WorkspacePackage {
+                name: "faucet".to_owned(),
+                manifest_path,
+                targets: vec![Target {
+                    name: "faucet".to_owned(),
+                    crate_types: vec!["cdylib".to_owned(), "lib".to_owned()],
+                }],
+            }],
+            target_directory: dir.join("target"),
+        };
+
+        // Commented out ids & other source files are ignored.
+        let keypair = Keypair::new();
+        std::fs::write(
+            dir.join("programs/faucet/src/lib.rs"),
+            format!(
+                "// declare_id!(\"{}\");\ndeclare_id!(\"{}\");\n",
+                Pubkey::new_unique(),
+                keypair.pubkey()
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("programs/faucet/src/other.rs"),
+            format!("declare_id!(\"{}\");\n", Pubkey::new_unique()),
+        )
+        .unwrap();
+        assert_eq!(program_id(&metadata, "faucet"), keypair.pubkey());
+
+        // The deploy keypair must match.
+        write_keypair_file(&keypair, dir.join("target/deploy/faucet-keypair.json")).unwrap();
+        assert_eq!(program_id(&metadata, "faucet"), keypair.pubkey());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn stale_check_skips_pinned_elf() {
+        // A pinned ELF sharing the faucet's name predates its sources.
+        let dir = std::env::temp_dir().join(format!("svm-test-pinned-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let elf = dir.join("faucet.so");
+        std::fs::File::create(&elf)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        assert_eq!(check_stale_elf("faucet", &elf), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -51,28 +51,36 @@ where
 {
     fn drop(&mut self) {
         if self.dirty {
-            if let Some(path) = &self.path {
-                try_write_json_gz(path, &self.data);
+            match &self.path {
+                Some(path) if path.extension().is_some_and(|ext| ext == "gz") => {
+                    try_write_json_gz(path, &self.data)
+                }
+                Some(path) => try_write_json(path, &self.data),
+                None => {}
             }
         }
     }
 }
 
+pub fn try_write_json<T>(path: &Path, data: &T)
+where
+    T: Serialize,
+{
+    let Some(file) = try_open_write(path) else {
+        return;
+    };
+
+    if let Err(err) = serde_json::to_writer_pretty(file, &data) {
+        error!("Failed to serialize data; path={path:?}; err={err}");
+    }
+}
+
 pub fn try_write_json_gz<T>(path: &Path, data: &T)
 where
     T: Serialize,
 {
-    let file = match std::fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(path)
-    {
-        Ok(file) => file,
-        Err(err) => {
-            error!("Failed to write to file; path={path:?}; err={err}");
-            return;
-        }
+    let Some(file) = try_open_write(path) else {
+        return;
     };
     let compression = GzEncoder::new(file, flate2::Compression::best());
 
@@ -83,3 +91,13 @@ where
         }
     }
 }
+
+fn try_open_write(path: &Path) -> Option<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(path)
+        .inspect_err(|err| error!("Failed to write to file; path={path:?}; err={err}"))
+        .ok()
+}
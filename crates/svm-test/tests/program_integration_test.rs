@@ -3,12 +3,12 @@
 //! deployed testnet/mainnet accounts.
 use expect_test::expect;
 use litesvm::types::SimulatedTransactionInfo;
-use solana_sdk::account::{Account, ReadableAccount};
+use solana_sdk::account::Account;
 use solana_sdk::instruction::{AccountMeta, Instruction};
-use solana_sdk::program_pack::Pack;
 use solana_sdk::pubkey;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::transaction::Transaction;
+use svm_test::utils::spl_token::MintBuilder;
 use svm_test::utils::{test_payer_keypair, TEST_PAYER};
 use svm_test::{Harness, Svm};
 
@@ -22,9 +22,14 @@ fn faucet() {
 
     // Setup our faucet.
     svm.load_program(faucet::ID, "faucet");
+    svm_test::utils::spl_token::mock_mint(&mut svm, USDC, &MintBuilder::new(6));
     let faucet_usdc =
         svm_test::utils::spl_token::mock_ata(&mut svm, USDC, faucet::SPENDER, 5 * 10u64.pow(6));
     let recipient_usdc = svm_test::utils::spl_token::mock_ata(&mut svm, USDC, TEST_PAYER, 0);
+    svm.address_book.insert(faucet::ID, "faucet");
+    svm.address_book.insert(faucet::SPENDER, "spender");
+    svm.address_book.insert(faucet_usdc, "spender_usdc");
+    svm.address_book.insert(recipient_usdc, "test_payer_usdc");
 
     // Prepare a basic transaction.
     let ixs = [Instruction::new_with_bytes(
@@ -45,20 +50,21 @@ fn faucet() {
     );
 
     // Simulate (run without updating state).
-    let SimulatedTransactionInfo { meta, post_accounts } = svm.simulate_transaction(tx).unwrap();
+    let (result, balances) = svm.simulate_transaction_with_token_balances(tx);
+    let SimulatedTransactionInfo { meta, post_accounts } = result.unwrap();
 
     // Assert.
     expect![[r#"
         TransactionMetadata {
             signature: 3fUbwymETnKUx2ijUcW6VT4FVTm6JM6sAypyfUAzekHUWjP6MoKGma1MG2tmpHVnfbJ3v2aD1WkgcGPmHF4dUSta,
             logs: [
-                "Program 69jHfHKn5N6sw9ZacqFzVVhETGiRkh9LD3q9YrfmAA6v invoke [1]",
-                "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+                "Program faucet (69jH…) invoke [1]",
+                "Program spl_token (Toke…) invoke [2]",
                 "Program log: Instruction: Transfer",
-                "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA consumed 4645 of 197274 compute units",
-                "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
-                "Program 69jHfHKn5N6sw9ZacqFzVVhETGiRkh9LD3q9YrfmAA6v consumed 7515 of 200000 compute units",
-                "Program 69jHfHKn5N6sw9ZacqFzVVhETGiRkh9LD3q9YrfmAA6v success",
+                "Program spl_token (Toke…) consumed 4645 of 197274 compute units",
+                "Program spl_token (Toke…) success",
+                "Program faucet (69jH…) consumed 7515 of 200000 compute units",
+                "Program faucet (69jH…) success",
             ],
             inner_instructions: [
                 [
@@ -88,79 +94,56 @@ fn faucet() {
             ],
             compute_units_consumed: 7515,
             return_data: TransactionReturnData {
-                program_id: TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA,
+                program_id: spl_token (Toke…),
                 data: [],
             },
         }
-    "#]].assert_debug_eq(&meta);
+    "#]].assert_eq(&format!("{}\n", svm.render(&meta)));
     expect![[r#"
         [
             (
-                AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9,
+                test_payer (AKnL…),
                 Account {
                     lamports: 999995000,
                     data.len: 0,
-                    owner: 11111111111111111111111111111111,
+                    owner: system_program (1111…),
                     executable: false,
                     rent_epoch: 0,
                 },
             ),
             (
-                3wvJdyFnGvaMWpbq93NU91SggiVRveULUXL6iX5VZDGP,
+                test_payer_usdc (3wvJ…),
                 Account {
                     lamports: 2039280,
                     data.len: 165,
-                    owner: TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA,
+                    owner: spl_token (Toke…),
                     executable: false,
                     rent_epoch: 0,
                     data: c6fa7af3bedbad3a3d65f36aabc97431b1bbe4c2d2f6e0e47ca60203452f5d618a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c,
                 },
             ),
             (
-                Acf7QyKKBaV4QSR1aBCEAEvPWTreSmqSQokQnQxvic5N,
+                spender_usdc (Acf7…),
                 Account {
                     lamports: 2039280,
                     data.len: 165,
-                    owner: TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA,
+                    owner: spl_token (Toke…),
                     executable: false,
                     rent_epoch: 0,
                     data: c6fa7af3bedbad3a3d65f36aabc97431b1bbe4c2d2f6e0e47ca60203452f5d613550b298afcde8c44442accf805d2639b99c76f1b250c3692caad5ecbb14928b,
                 },
             ),
         ]
-    "#]].assert_debug_eq(&post_accounts);
+    "#]].assert_eq(&format!("{}\n", svm.render(&post_accounts)));
 
-    // TODO: Consider making a helper for this.
-    let faucet_before = spl_token::state::Account::unpack(&svm.get(&faucet_usdc).unwrap().data)
-        .unwrap()
-        .amount;
-    let faucet_after = spl_token::state::Account::unpack(
-        post_accounts
-            .iter()
-            .find(|(key, _)| key == &faucet_usdc)
-            .unwrap()
-            .1
-            .data(),
-    )
-    .unwrap()
-    .amount;
-    assert_eq!(faucet_before, 5 * 10u64.pow(6));
-    assert_eq!(faucet_after, 4 * 10u64.pow(6));
-
-    let recipient_before =
-        spl_token::state::Account::unpack(&svm.get(&recipient_usdc).unwrap().data)
-            .unwrap()
-            .amount;
-    let recipient_after = spl_token::state::Account::unpack(
-        post_accounts
-            .iter()
-            .find(|(key, _)| key == &recipient_usdc)
-            .unwrap()
-            .1
-            .data(),
-    )
-    .unwrap()
-    .amount;
-    assert_eq!(recipient_before, 0);
-    assert_eq!(recipient_after, 10u64.pow(6));
+    // Assert token balance changes.
+    balances.assert_sent(&faucet::SPENDER, &USDC, 10u64.pow(6));
+    balances.assert_received(&TEST_PAYER, &USDC, 10u64.pow(6));
+    expect![[r#"
+        [
+            "test_payer_usdc (3wvJ…) (test_payer (AKnL…)): 0 -> 1 USDC (EPjF…)",
+            "spender_usdc (Acf7…) (spender (4b7y…)): 5 -> 4 USDC (EPjF…)",
+        ]
+    "#]]
+    .assert_debug_eq(&balances.changes());
 }
@@ -3,11 +3,13 @@
 use expect_test::expect;
 use litesvm::types::SimulatedTransactionInfo;
 use solana_sdk::account::Account;
+use solana_sdk::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
 use solana_sdk::instruction::Instruction;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::transaction::Transaction;
+use svm_test::differential::{Mismatch, ProgramDiff};
 use svm_test::svm::DefaultLoader;
-use svm_test::utils::{test_payer_keypair, TEST_PAYER};
+use svm_test::utils::{load_program_elf, test_payer_keypair, TEST_PAYER};
 use svm_test::Svm;
 
 const MEMO_ID: Pubkey = Pubkey::new_from_array([1; 32]);
@@ -66,3 +68,158 @@ fn memo() {
     "#]]
     .assert_debug_eq(&post_accounts);
 }
+
+#[test]
+fn upgradeable_memo() {
+    let mut svm: Svm<DefaultLoader> = Svm::default();
+    let authority = Pubkey::new_unique();
+    svm.load_upgradeable_program(MEMO_ID, "memo", Some(authority), 5);
+    svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+
+    // Program data holds our authority & deploy slot.
+    let programdata = svm
+        .get(&bpf_loader_upgradeable::get_program_data_address(&MEMO_ID))
+        .unwrap();
+    assert_eq!(
+        bincode::deserialize::<UpgradeableLoaderState>(&programdata.data).unwrap(),
+        UpgradeableLoaderState::ProgramData { slot: 5, upgrade_authority_address: Some(authority) }
+    );
+
+    // The program can be invoked as usual.
+    let ixs = [Instruction::new_with_bytes(MEMO_ID, b"hello", vec![])];
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&TEST_PAYER),
+        &[test_payer_keypair()],
+        svm.blockhash(),
+    );
+    svm.simulate_transaction(tx).unwrap();
+}
+
+#[test]
+fn upgrade_and_close_memo() {
+    let mut svm: Svm<DefaultLoader> = Svm::default();
+    svm.load_upgradeable_program(MEMO_ID, "memo", Some(Pubkey::new_unique()), 5);
+    svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+    let ixs = [Instruction::new_with_bytes(MEMO_ID, b"hello", vec![])];
+    let tx = |svm: &Svm<DefaultLoader>| {
+        Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&TEST_PAYER),
+            &[test_payer_keypair()],
+            svm.blockhash(),
+        )
+    };
+    svm.simulate_transaction(tx(&svm)).unwrap();
+
+    // Upgrading swaps the ELF, the faucet expects accounts & fails.
+    svm.upgrade_program(MEMO_ID, "faucet");
+    svm.simulate_transaction(tx(&svm)).unwrap_err();
+
+    // Closing (after the deploy slot) empties the program data into the
+    // recipient & the program can no longer be invoked.
+    svm.upgrade_program(MEMO_ID, "memo");
+    svm.simulate_transaction(tx(&svm)).unwrap();
+    let programdata_address = bpf_loader_upgradeable::get_program_data_address(&MEMO_ID);
+    let lamports = svm.get(&programdata_address).unwrap().lamports;
+    let recipient = Pubkey::new_unique();
+    svm.warp_to_slot(1);
+    svm.close_program(MEMO_ID, recipient);
+    let programdata = svm.get(&programdata_address);
+    assert_eq!(programdata.map_or(0, |account| account.lamports), 0);
+    assert_eq!(svm.get(&recipient).unwrap().lamports, lamports);
+    assert!(svm.get(&MEMO_ID).unwrap().executable);
+    svm.simulate_transaction(tx(&svm)).unwrap_err();
+}
+
+#[test]
+fn override_faucet_with_memo() {
+    let authority = Pubkey::new_unique();
+    let programdata_address = bpf_loader_upgradeable::get_program_data_address(&MEMO_ID);
+    let ixs = [Instruction::new_with_bytes(MEMO_ID, b"hello", vec![])];
+    let tx = |svm: &Svm<DefaultLoader>| {
+        Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&TEST_PAYER),
+            &[test_payer_keypair()],
+            svm.blockhash(),
+        )
+    };
+    let header = |svm: &Svm<DefaultLoader>| {
+        let programdata = svm.get(&programdata_address).unwrap();
+
+        bincode::deserialize::<UpgradeableLoaderState>(&programdata.data).unwrap()
+    };
+
+    // A deployed program, the faucet expects accounts & fails.
+    let mut svm: Svm<DefaultLoader> = Svm::default();
+    svm.load_upgradeable_program(MEMO_ID, "faucet", Some(authority), 5);
+    svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+    svm.simulate_transaction(tx(&svm)).unwrap_err();
+
+    // Overriding runs the new code & keeps the authority & deploy slot.
+    svm.override_program(MEMO_ID, "memo");
+    svm.simulate_transaction(tx(&svm)).unwrap();
+    assert_eq!(
+        header(&svm),
+        UpgradeableLoaderState::ProgramData { slot: 5, upgrade_authority_address: Some(authority) }
+    );
+
+    // A program only known to the loader (i.e. a scenario) can be overridden
+    // before it has been touched.
+    let mut deployed: Svm<DefaultLoader> = Svm::default();
+    deployed.load_upgradeable_program(MEMO_ID, "faucet", Some(authority), 5);
+    let loader: DefaultLoader = [MEMO_ID, programdata_address]
+        .into_iter()
+        .map(|key| (key, deployed.get(&key).unwrap()))
+        .collect();
+    let mut svm = Svm::new(loader);
+    svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+    svm.override_program_elf(MEMO_ID, &load_program_elf("memo"));
+    svm.simulate_transaction(tx(&svm)).unwrap();
+    assert_eq!(
+        header(&svm),
+        UpgradeableLoaderState::ProgramData { slot: 5, upgrade_authority_address: Some(authority) }
+    );
+}
+
+#[test]
+#[should_panic(expected = "Program is immutable")]
+fn close_immutable_memo() {
+    let mut svm: Svm<DefaultLoader> = Svm::default();
+    svm.load_upgradeable_program(MEMO_ID, "memo", None, 0);
+    svm.warp_to_slot(1);
+
+    svm.close_program(MEMO_ID, Pubkey::new_unique());
+}
+
+#[test]
+fn memo_vs_faucet() {
+    let mut svm: Svm<DefaultLoader> = Svm::default();
+    let memo = load_program_elf("memo");
+    svm.set(TEST_PAYER, Account { lamports: 10u64.pow(9), ..Default::default() });
+
+    let ixs = [Instruction::new_with_bytes(MEMO_ID, b"hello", vec![])];
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&TEST_PAYER),
+        &[test_payer_keypair()],
+        svm.blockhash(),
+    );
+
+    // Identical builds do not diverge.
+    let diff = ProgramDiff::new(MEMO_ID, memo.clone(), memo.clone());
+    assert!(diff.run(&mut svm, [tx.clone()]).is_empty());
+
+    // The faucet expects accounts & fails.
+    let diff = ProgramDiff::new(MEMO_ID, memo, load_program_elf("faucet")).allow_compute_units();
+    let divergences = diff.run(&mut svm, [tx]);
+    assert_eq!(divergences.len(), 1);
+    assert!(matches!(
+        divergences[0].mismatches[0],
+        Mismatch::Result { left: None, right: Some(_) }
+    ));
+
+    // State is left untouched.
+    assert_eq!(svm.get(&TEST_PAYER).unwrap().lamports, 10u64.pow(9));
+}